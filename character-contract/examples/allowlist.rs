@@ -0,0 +1,55 @@
+/*
+    builds the merkle tree of the presale allowlist off-chain.
+
+    usage: cargo run --example allowlist -- <allowlist.csv>
+
+    every line of the input is "<account_id>,<quota>". The output is a JSON object with the base64 root
+    to pass into `set_allowlist_root` and the `allowlist_proof` argument of `nft_mint` for every account.
+*/
+use std::collections::BTreeMap;
+use std::{env, fs};
+
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::AccountId;
+use pray_character::{allowlist_leaf, AllowlistProof, MerkleTree};
+use serde_json::json;
+
+fn main() {
+    let path = env::args().nth(1).expect("usage: allowlist <allowlist.csv>");
+    let contents = fs::read_to_string(&path).expect("failed to read the allowlist");
+
+    let entries: Vec<(AccountId, u32)> = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (account_id, quota) = line.split_once(',').expect("expected <account_id>,<quota>");
+            (
+                account_id.trim().parse().expect("invalid account id"),
+                quota.trim().parse().expect("invalid quota"),
+            )
+        })
+        .collect();
+
+    let tree = MerkleTree::new(
+        entries
+            .iter()
+            .map(|(account_id, quota)| allowlist_leaf(account_id, *quota))
+            .collect(),
+    );
+
+    let proofs: BTreeMap<String, AllowlistProof> = entries
+        .iter()
+        .enumerate()
+        .map(|(index, (account_id, quota))| {
+            let proof = tree.proof(index).iter().map(|node| Base64VecU8(node.to_vec())).collect();
+            (account_id.to_string(), AllowlistProof { quota: *quota, proof })
+        })
+        .collect();
+
+    let output = json!({
+        "root": Base64VecU8(tree.root().to_vec()),
+        "proofs": proofs,
+    });
+    println!("{}", serde_json::to_string_pretty(&output).unwrap());
+}
@@ -0,0 +1,85 @@
+use crate::*;
+
+//narrow admin powers the owner can delegate without sharing the owner key
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Role {
+    //uploads the encrypted metadata, commits to its provenance and reveal key and publishes the collection
+    MetadataManager,
+    //submits the reveal key, reveals the metadata and marks the collection as revealed
+    Revealer,
+    //manages the mint price, the sale phases and the allowlists
+    SaleManager,
+    //withdraws the sale proceeds to the owner
+    Treasurer,
+}
+
+//a role granted to an account, as returned by `roles`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RoleGrant {
+    pub role: Role,
+    pub account_id: AccountId,
+}
+
+#[near_bindgen]
+impl Contract {
+    //propose a new owner, e.g. a DAO, who has to accept the ownership. Passing None cancels the proposal
+    pub fn propose_owner(&mut self, new_owner_id: Option<AccountId>) {
+        self.assert_called_by_owner();
+
+        self.pending_owner_id = new_owner_id;
+    }
+
+    //accept the ownership proposed by the current owner. The roles granted by the previous owner are revoked
+    pub fn accept_ownership(&mut self) {
+        let pending_owner_id = self.pending_owner_id.take().expect("No ownership transfer was proposed");
+        assert_eq!(
+            env::predecessor_account_id(),
+            pending_owner_id,
+            "Only the proposed owner can accept the ownership"
+        );
+
+        self.owner_id = pending_owner_id;
+        self.roles.clear();
+    }
+
+    pub fn owner_id(&self) -> AccountId {
+        self.owner_id.clone()
+    }
+
+    pub fn pending_owner_id(&self) -> Option<AccountId> {
+        self.pending_owner_id.clone()
+    }
+
+    pub fn grant_role(&mut self, role: Role, account_id: AccountId) {
+        self.assert_called_by_owner();
+
+        self.roles.insert(&(role, account_id));
+    }
+
+    pub fn revoke_role(&mut self, role: Role, account_id: AccountId) {
+        self.assert_called_by_owner();
+
+        self.roles.remove(&(role, account_id));
+    }
+
+    //view whether the role was granted to the account. The owner can call every admin method regardless of the roles
+    pub fn has_role(&self, role: Role, account_id: AccountId) -> bool {
+        self.roles.contains(&(role, account_id))
+    }
+
+    //view the granted roles using pagination
+    pub fn roles(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<RoleGrant> {
+        //where to start pagination - if we have a from_index, we'll use that - otherwise start from 0 index
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+
+        self.roles
+            .iter()
+            .skip(start as usize)
+            //take the first "limit" elements. If we didn't specify a limit, use 50
+            .take(limit.unwrap_or(50) as usize)
+            .map(|(role, account_id)| RoleGrant { role, account_id })
+            .collect()
+    }
+}
@@ -43,6 +43,7 @@ impl NonFungibleTokenCore for Contract {
             The user needs to attach enough to pay for storage on the contract
         */
         assert_at_least_one_yocto();
+        assert!(!self.pause_flags.approve, "Approvals are paused");
 
         //get the token object from the token ID
         let mut token = self.tokens_by_id.get(&token_id).expect("No token");
@@ -81,6 +82,24 @@ impl NonFungibleTokenCore for Contract {
         //refund any excess storage attached by the user. If the user didn't attach enough, panic. 
         refund_deposit(storage_used);
 
+        // Construct the approve log as per the events standard.
+        let nft_approve_log: EventLog = EventLog {
+            // Standard name ("nep171").
+            standard: NFT_STANDARD_NAME.to_string(),
+            // Version of the standard ("nft-1.0.0").
+            version: NFT_METADATA_SPEC.to_string(),
+            // The data related with the event stored in a vector.
+            event: EventLogVariant::NftApprove(vec![NftApproveLog {
+                owner_id: token.owner_id.to_string(),
+                account_id: account_id.to_string(),
+                approval_id,
+                token_ids: vec![token_id.to_string()],
+            }]),
+        };
+
+        // Log the serialized json.
+        env::log_str(&nft_approve_log.to_string());
+
         //if some message was passed into the function, we initiate a cross contract call on the
         //account we're giving access to. 
         if let Some(msg) = msg {
@@ -143,10 +162,27 @@ impl NonFungibleTokenCore for Contract {
             .is_some()
         {
             //refund the funds released by removing the approved_account_id to the caller of the function
-            refund_approved_account_ids_iter(predecessor_account_id, [account_id].iter());
+            refund_approved_account_ids_iter(predecessor_account_id, [account_id.clone()].iter());
 
             //insert the token back into the tokens_by_id collection with the account_id removed from the approval list
             self.tokens_by_id.insert(&token_id, &token);
+
+            // Construct the revoke log as per the events standard.
+            let nft_revoke_log: EventLog = EventLog {
+                // Standard name ("nep171").
+                standard: NFT_STANDARD_NAME.to_string(),
+                // Version of the standard ("nft-1.0.0").
+                version: NFT_METADATA_SPEC.to_string(),
+                // The data related with the event stored in a vector.
+                event: EventLogVariant::NftRevoke(vec![NftRevokeLog {
+                    owner_id: token.owner_id.to_string(),
+                    account_id: account_id.to_string(),
+                    token_ids: vec![token_id.to_string()],
+                }]),
+            };
+
+            // Log the serialized json.
+            env::log_str(&nft_revoke_log.to_string());
         }
     }
 
@@ -170,6 +206,22 @@ impl NonFungibleTokenCore for Contract {
             token.approved_account_ids.clear();
             //insert the token back into the tokens_by_id collection with the approved account IDs cleared
             self.tokens_by_id.insert(&token_id, &token);
+
+            // Construct the revoke all log as per the events standard.
+            let nft_revoke_all_log: EventLog = EventLog {
+                // Standard name ("nep171").
+                standard: NFT_STANDARD_NAME.to_string(),
+                // Version of the standard ("nft-1.0.0").
+                version: NFT_METADATA_SPEC.to_string(),
+                // The data related with the event stored in a vector.
+                event: EventLogVariant::NftRevokeAll(vec![NftRevokeAllLog {
+                    owner_id: token.owner_id.to_string(),
+                    token_ids: vec![token_id.to_string()],
+                }]),
+            };
+
+            // Log the serialized json.
+            env::log_str(&nft_revoke_all_log.to_string());
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,71 @@
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /*
+        burn a token on behalf of its owner. The owner or an approved account can burn it,
+        the storage the owner paid for, the token with its approvals and its entry in the owner's set, is refunded to the owner.
+        the revealed metadata and stats were stored by the contract, so their storage isn't refunded.
+        burned token ids are gone from the pool of unminted ids, so they are never minted again.
+    */
+    #[payable]
+    pub fn nft_burn(&mut self, token_id: TokenId, approval_id: Option<u64>, memo: Option<String>) {
+        //assert that the user attached exactly 1 yoctoNEAR. This is for security and so that the user will be redirected to the NEAR wallet. 
+        assert_one_yocto();
+        assert!(!self.pause_flags.transfer, "Transfers are paused");
+        let sender_id = env::predecessor_account_id();
+        let token = self.tokens_by_id.get(&token_id).expect("No token");
+        assert!(self.character_locations.get(&token_id).is_none(), "Character must leave its location before it is burned");
+
+        //if the sender doesn't equal the owner, we check if the sender is in the approval list
+        let mut authorized_id = None;
+        if sender_id != token.owner_id {
+            let actual_approval_id = token.approved_account_ids.get(&sender_id).expect("Unauthorized");
+
+            //if they included an approval_id, make sure it matches the actual one
+            if let Some(enforced_approval_id) = approval_id {
+                assert_eq!(
+                    actual_approval_id, &enforced_approval_id,
+                    "The actual approval_id {} is different from the given approval_id {}",
+                    actual_approval_id, enforced_approval_id,
+                );
+            }
+            authorized_id = Some(sender_id.to_string());
+        }
+
+        //remove the token with its approvals, measuring the storage that is released
+        let initial_storage_usage = env::storage_usage();
+        self.internal_remove_token_from_owner(&token.owner_id, &token_id);
+        self.tokens_by_id.remove(&token_id);
+        let storage_released = initial_storage_usage - env::storage_usage();
+
+        //the metadata and stats are removed as well, but their storage goes back to the contract
+        self.token_metadata_by_id.remove(&token_id);
+        self.character_stats.remove(&token_id);
+
+        //refund the released storage to the owner of the token
+        Promise::new(token.owner_id.clone()).transfer(Balance::from(storage_released) * env::storage_byte_cost());
+
+        // Construct the burn log as per the events standard.
+        let nft_burn_log: EventLog = EventLog {
+            // Standard name ("nep171").
+            standard: NFT_STANDARD_NAME.to_string(),
+            // Version of the standard ("nft-1.0.0").
+            version: NFT_METADATA_SPEC.to_string(),
+            // The data related with the event stored in a vector.
+            event: EventLogVariant::NftBurn(vec![NftBurnLog {
+                // Owner of the burned token.
+                owner_id: token.owner_id.to_string(),
+                // The optional approved account ID which burned the token on behalf of the owner.
+                authorized_id,
+                // Vector of token IDs that were burned.
+                token_ids: vec![token_id],
+                // An optional memo to include.
+                memo,
+            }]),
+        };
+
+        // Log the serialized json.
+        env::log_str(&nft_burn_log.to_string());
+    }
+}
@@ -0,0 +1,174 @@
+use crate::*;
+use aes_gcm::{
+    aead::{generic_array::GenericArray, Aead, Payload},
+    NewAead,
+    Aes256Gcm,
+};
+use pbkdf2::{
+    password_hash::{
+        PasswordHasher, SaltString
+    },
+    Pbkdf2,
+    Algorithm,
+    Params,
+};
+
+/*
+    encrypted metadata format (base64 encoded):
+    magic "PRAY" | version (1 byte) | PBKDF2-SHA256 rounds (u32, little endian) | salt (16 bytes) | nonce (12 bytes) | ciphertext
+    legacy payloads have no header and were derived with a single round: salt | nonce | ciphertext
+*/
+pub const METADATA_MAGIC: &[u8; 4] = b"PRAY";
+pub const METADATA_FORMAT_VERSION: u8 = 1;
+//minimum number of key derivation rounds accepted in a versioned payload
+pub const MIN_KDF_ROUNDS: u32 = 1_000;
+//maximum number of key derivation rounds, deriving the key has to fit into the gas of a single call
+pub const MAX_KDF_ROUNDS: u32 = 20_000;
+const LEGACY_KDF_ROUNDS: u32 = 1;
+const HEADER_LEN: usize = 9;
+
+//key derivation parameters of a payload. Every batch of a collection shares them, so that the key is derived once
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, PartialEq)]
+pub struct KdfParams {
+    pub rounds: u32,
+    pub salt: [u8; 16],
+}
+
+//AES-256 key derived from the reveal key, together with the parameters it was derived with
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, PartialEq)]
+pub struct DerivedKey {
+    pub params: KdfParams,
+    pub key: [u8; 32],
+}
+
+impl DerivedKey {
+    pub fn derive(password: &str, params: KdfParams) -> Result<Self, RevealError> {
+        let key = derive_key(password, &params.salt, params.rounds)?;
+        Ok(DerivedKey { params, key })
+    }
+}
+
+//read the key derivation parameters of a payload, which also checks it before it is uploaded
+pub fn kdf_params(encrypted: &str) -> Result<KdfParams, RevealError> {
+    //whether legacy payloads can be revealed is decided when revealing
+    decode_payload(encrypted, true).map(|(params, _)| params)
+}
+
+//decrypt the metadata batch with the password. Legacy payloads are only decrypted when allowed explicitly
+pub fn aes_gcm_decrypt(password: &str, encrypted: &str, allow_legacy: bool) -> Result<String, RevealError> {
+    let (params, body) = decode_payload(encrypted, allow_legacy)?;
+    let key = DerivedKey::derive(password, params)?;
+    decrypt_body(&key, &body)
+}
+
+//decrypt the metadata batch with a key derived beforehand, which only costs the AES-GCM decryption
+pub fn aes_gcm_decrypt_with_key(key: &DerivedKey, encrypted: &str, allow_legacy: bool) -> Result<String, RevealError> {
+    let (params, body) = decode_payload(encrypted, allow_legacy)?;
+    if params != key.params {
+        return Err(RevealError::KdfMismatch);
+    }
+    decrypt_body(key, &body)
+}
+
+fn decrypt_body(key: &DerivedKey, body: &[u8]) -> Result<String, RevealError> {
+    let nonce = GenericArray::from_slice(&body[0..12]);
+    let ciphertext = &body[12..];
+
+    let cipher = Aes256Gcm::new(GenericArray::from_slice(&key.key));
+    let payload = Payload { msg: ciphertext, aad: b"", };
+    let plaintext = cipher.decrypt(nonce, payload).map_err(|_| RevealError::Decryption)?;
+    String::from_utf8(plaintext).map_err(|_| RevealError::InvalidUtf8)
+}
+
+//decode the payload into the key derivation parameters and the rest of its body: nonce | ciphertext
+fn decode_payload(encrypted: &str, allow_legacy: bool) -> Result<(KdfParams, Vec<u8>), RevealError> {
+    let mut encrypted_b64 = base64::decode(encrypted.as_bytes()).map_err(|_| RevealError::InvalidBase64)?;
+
+    let rounds = if encrypted_b64.starts_with(METADATA_MAGIC) {
+        if encrypted_b64.len() < HEADER_LEN {
+            return Err(RevealError::TooShort);
+        }
+        match encrypted_b64[4] {
+            METADATA_FORMAT_VERSION => {
+                let mut rounds = [0u8; 4];
+                rounds.copy_from_slice(&encrypted_b64[5..HEADER_LEN]);
+                let rounds = u32::from_le_bytes(rounds);
+                check_kdf_rounds(rounds)?;
+                encrypted_b64.drain(..HEADER_LEN);
+                rounds
+            },
+            version => return Err(RevealError::UnsupportedVersion(version)),
+        }
+    } else if allow_legacy {
+        LEGACY_KDF_ROUNDS
+    } else {
+        return Err(RevealError::LegacyFormat);
+    };
+
+    if encrypted_b64.len() < 28 {
+        return Err(RevealError::TooShort);
+    }
+    let mut salt = [0u8; 16];
+    salt.copy_from_slice(&encrypted_b64[..16]);
+    encrypted_b64.drain(..16);
+    Ok((KdfParams { rounds, salt }, encrypted_b64))
+}
+
+//rounds are bounded below for the key to be hard to brute force and above for it to be derivable on-chain
+pub fn check_kdf_rounds(rounds: u32) -> Result<(), RevealError> {
+    if rounds < MIN_KDF_ROUNDS {
+        return Err(RevealError::WeakKeyDerivation);
+    }
+    if rounds > MAX_KDF_ROUNDS {
+        return Err(RevealError::ExcessiveKeyDerivation);
+    }
+    Ok(())
+}
+
+//derive the AES-256 key from the password with PBKDF2-SHA256
+pub fn derive_key(password: &str, salt: &[u8], rounds: u32) -> Result<[u8; 32], RevealError> {
+    let salt = SaltString::b64_encode(salt).map_err(|_| RevealError::InvalidSalt)?;
+    let password_hash = Pbkdf2.hash_password_customized(
+        password.as_bytes(),
+        Some(Algorithm::Pbkdf2Sha256.ident()),
+        None,
+        Params {
+            rounds,
+            output_length: 32,
+        },
+        &salt
+    ).map_err(|_| RevealError::KeyDerivation)?;
+    let hash = password_hash.hash.ok_or(RevealError::KeyDerivation)?;
+
+    let mut key = [0u8; 32];
+    key.copy_from_slice(hash.as_bytes());
+    Ok(key)
+}
+
+/*
+    encrypt the metadata batch with the password in the current format.
+    randomness isn't available on-chain, so the caller provides the salt and the nonce.
+*/
+pub fn aes_gcm_encrypt(
+    password: &str,
+    plaintext: &str,
+    rounds: u32,
+    salt: &[u8; 16],
+    nonce: &[u8; 12],
+) -> Result<String, RevealError> {
+    check_kdf_rounds(rounds)?;
+
+    let key = derive_key(password, salt, rounds)?;
+    let cipher = Aes256Gcm::new(GenericArray::from_slice(&key));
+    let payload = Payload { msg: plaintext.as_bytes(), aad: b"", };
+    let ciphertext = cipher.encrypt(GenericArray::from_slice(nonce), payload).map_err(|_| RevealError::Encryption)?;
+
+    let mut encrypted = Vec::with_capacity(HEADER_LEN + 28 + ciphertext.len());
+    encrypted.extend_from_slice(METADATA_MAGIC);
+    encrypted.push(METADATA_FORMAT_VERSION);
+    encrypted.extend_from_slice(&rounds.to_le_bytes());
+    encrypted.extend_from_slice(salt);
+    encrypted.extend_from_slice(nonce);
+    encrypted.extend_from_slice(&ciphertext);
+    Ok(base64::encode(encrypted))
+}
@@ -1,5 +1,8 @@
 use crate::*;
 
+//largest page of tokens while metadata is revealed lazily, since every token of a page may decrypt a batch of its own
+pub const LAZY_REVEAL_PAGE_LIMIT: u64 = 10;
+
 #[near_bindgen]
 impl Contract {
     //Query for the total supply of NFTs on the contract
@@ -13,14 +16,17 @@ impl Contract {
         //where to start pagination - if we have a from_index, we'll use that - otherwise start from 0 index
         let start = u128::from(from_index.unwrap_or(U128(0)));
 
+        //batches decrypted while revealing metadata lazily, shared by all tokens of the page
+        let mut decrypted_batches = HashMap::new();
+
         //iterate through each token using an iterator
         self.tokens_by_id.keys()
             //skip to the index we specified in the start variable
             .skip(start as usize) 
             //take the first "limit" elements in the vector. If we didn't specify a limit, use 50
-            .take(limit.unwrap_or(50) as usize) 
+            .take(self.internal_page_limit(limit)) 
             //we'll map the token IDs which are strings into Json Tokens
-            .map(|token_id| self.nft_token(token_id.clone()).unwrap())
+            .map(|token_id| self.internal_json_token(token_id.clone(), &mut decrypted_batches).unwrap())
             //since we turned the keys into an iterator, we need to turn it back into a vector to return
             .collect()
     }
@@ -62,15 +68,29 @@ impl Contract {
         //where to start pagination - if we have a from_index, we'll use that - otherwise start from 0 index
         let start = u128::from(from_index.unwrap_or(U128(0)));
 
+        //batches decrypted while revealing metadata lazily, shared by all tokens of the page
+        let mut decrypted_batches = HashMap::new();
+
         //iterate through the keys vector
         tokens.iter()
             //skip to the index we specified in the start variable
             .skip(start as usize) 
             //take the first "limit" elements in the vector. If we didn't specify a limit, use 50
-            .take(limit.unwrap_or(50) as usize) 
+            .take(self.internal_page_limit(limit)) 
             //we'll map the token IDs which are strings into Json Tokens
-            .map(|token_id| self.nft_token(token_id.clone()).unwrap())
+            .map(|token_id| self.internal_json_token(token_id.clone(), &mut decrypted_batches).unwrap())
             //since we turned the keys into an iterator, we need to turn it back into a vector to return
             .collect()
     }
 }
+
+impl Contract {
+    //number of tokens returned by a page of the enumeration
+    pub(crate) fn internal_page_limit(&self, limit: Option<u64>) -> usize {
+        let limit = limit.unwrap_or(50);
+        if self.lazy_reveal_batch_size.is_some() && self.reveal_key.is_some() {
+            return limit.min(LAZY_REVEAL_PAGE_LIMIT) as usize;
+        }
+        limit as usize
+    }
+}
@@ -2,8 +2,11 @@ use std::fmt;
 
 use near_sdk::serde::{Deserialize, Serialize};
 
+use crate::PauseFlags;
+
 /// Enum that represents the data type of the EventLog.
-/// The enum can either be an NftMint or an NftTransfer.
+/// The enum can be an NftMint, NftTransfer, NftBurn, an approval change, a metadata update,
+/// a metadata update of the whole collection or a change of the pause flags.
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(tag = "event", content = "data")]
 #[serde(rename_all = "snake_case")]
@@ -12,6 +15,13 @@ use near_sdk::serde::{Deserialize, Serialize};
 pub enum EventLogVariant {
     NftMint(Vec<NftMintLog>),
     NftTransfer(Vec<NftTransferLog>),
+    NftBurn(Vec<NftBurnLog>),
+    NftApprove(Vec<NftApproveLog>),
+    NftRevoke(Vec<NftRevokeLog>),
+    NftRevokeAll(Vec<NftRevokeAllLog>),
+    NftMetadataUpdate(Vec<NftMetadataUpdateLog>),
+    CollectionMetadataUpdate(Vec<CollectionMetadataUpdateLog>),
+    PauseFlagsUpdate(Vec<PauseFlags>),
 }
 
 /// Interface to capture data about an event
@@ -78,6 +88,90 @@ pub struct NftTransferLog {
     pub memo: Option<String>,
 }
 
+/// An event log to capture token burning
+///
+/// Arguments
+/// * `owner_id`: owner of the tokens, "owner.near"
+/// * `authorized_id`: approved account to burn
+/// * `token_ids`: ["1", "abc"]
+/// * `memo`: optional message
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftBurnLog {
+    pub owner_id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorized_id: Option<String>,
+
+    pub token_ids: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// An event log to capture an account approved to transfer tokens
+///
+/// Arguments
+/// * `owner_id`: "owner.near"
+/// * `account_id`: approved account, "market.near"
+/// * `approval_id`: 1
+/// * `token_ids`: ["1", "abc"]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftApproveLog {
+    pub owner_id: String,
+    pub account_id: String,
+    pub approval_id: u64,
+    pub token_ids: Vec<String>,
+}
+
+/// An event log to capture an approval which was revoked
+///
+/// Arguments
+/// * `owner_id`: "owner.near"
+/// * `account_id`: account which is no longer approved, "market.near"
+/// * `token_ids`: ["1", "abc"]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftRevokeLog {
+    pub owner_id: String,
+    pub account_id: String,
+    pub token_ids: Vec<String>,
+}
+
+/// An event log to capture all approvals of tokens being revoked
+///
+/// Arguments
+/// * `owner_id`: "owner.near"
+/// * `token_ids`: ["1", "abc"]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftRevokeAllLog {
+    pub owner_id: String,
+    pub token_ids: Vec<String>,
+}
+
+/// An event log to capture token metadata changes, e.g. when it is revealed
+///
+/// Arguments
+/// * `token_ids`: ["1", "abc"]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftMetadataUpdateLog {
+    pub token_ids: Vec<String>,
+}
+
+/// An event log to capture a metadata change of every token of the collection at once, e.g. when it is revealed lazily.
+/// Listing the token ids of a large collection wouldn't fit in the logs of a single call.
+///
+/// Arguments
+/// * `collection_size`: the token ids from "0" to `collection_size` - 1 are updated
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CollectionMetadataUpdateLog {
+    pub collection_size: u32,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -135,4 +229,121 @@ mod tests {
         };
         assert_eq!(expected, log.to_string());
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn nep_format_burn() {
+        let expected = r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_burn","data":[{"owner_id":"foundation.near","token_ids":["aurora","proximitylabs"]}]}"#;
+        let log = EventLog {
+            standard: "nep171".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::NftBurn(vec![NftBurnLog {
+                owner_id: "foundation.near".to_owned(),
+                authorized_id: None,
+                token_ids: vec!["aurora".to_string(), "proximitylabs".to_string()],
+                memo: None,
+            }]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn nep_format_burn_all_fields() {
+        let expected = r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_burn","data":[{"owner_id":"user1.near","authorized_id":"market.near","token_ids":["token"],"memo":"Go Team!"}]}"#;
+        let log = EventLog {
+            standard: "nep171".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::NftBurn(vec![NftBurnLog {
+                owner_id: "user1.near".to_owned(),
+                authorized_id: Some("market.near".to_string()),
+                token_ids: vec!["token".to_string()],
+                memo: Some("Go Team!".to_owned()),
+            }]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn nep_format_approve() {
+        let expected = r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_approve","data":[{"owner_id":"user1.near","account_id":"market.near","approval_id":3,"token_ids":["token"]}]}"#;
+        let log = EventLog {
+            standard: "nep171".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::NftApprove(vec![NftApproveLog {
+                owner_id: "user1.near".to_owned(),
+                account_id: "market.near".to_owned(),
+                approval_id: 3,
+                token_ids: vec!["token".to_string()],
+            }]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn nep_format_revoke() {
+        let expected = r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_revoke","data":[{"owner_id":"user1.near","account_id":"market.near","token_ids":["token"]}]}"#;
+        let log = EventLog {
+            standard: "nep171".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::NftRevoke(vec![NftRevokeLog {
+                owner_id: "user1.near".to_owned(),
+                account_id: "market.near".to_owned(),
+                token_ids: vec!["token".to_string()],
+            }]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn nep_format_revoke_all() {
+        let expected = r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_revoke_all","data":[{"owner_id":"user1.near","token_ids":["token"]}]}"#;
+        let log = EventLog {
+            standard: "nep171".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::NftRevokeAll(vec![NftRevokeAllLog {
+                owner_id: "user1.near".to_owned(),
+                token_ids: vec!["token".to_string()],
+            }]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn nep_format_metadata_update() {
+        let expected = r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_metadata_update","data":[{"token_ids":["aurora","proximitylabs"]}]}"#;
+        let log = EventLog {
+            standard: "nep171".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::NftMetadataUpdate(vec![NftMetadataUpdateLog {
+                token_ids: vec!["aurora".to_string(), "proximitylabs".to_string()],
+            }]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn pray_format_collection_metadata_update() {
+        let expected = r#"EVENT_JSON:{"standard":"pray","version":"1.0.0","event":"collection_metadata_update","data":[{"collection_size":10000}]}"#;
+        let log = EventLog {
+            standard: "pray".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::CollectionMetadataUpdate(vec![CollectionMetadataUpdateLog { collection_size: 10_000 }]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn pray_format_pause_flags_update() {
+        let expected = r#"EVENT_JSON:{"standard":"pray","version":"1.0.0","event":"pause_flags_update","data":[{"mint":true,"transfer":false,"approve":false,"move_character":true}]}"#;
+        let log = EventLog {
+            standard: "pray".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::PauseFlagsUpdate(vec![PauseFlags {
+                mint: true,
+                transfer: false,
+                approve: false,
+                move_character: true,
+            }]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+}
@@ -1,21 +1,6 @@
 use crate::*;
 use near_sdk::{CryptoHash};
-use std::str;
 use std::mem::size_of;
-use base64;
-use aes_gcm::{
-    aead::{generic_array::GenericArray, Aead, Payload},
-    NewAead,
-    Aes256Gcm,
-};
-use pbkdf2::{
-    password_hash::{
-        PasswordHasher, SaltString
-    },
-    Pbkdf2,
-    Algorithm,
-    Params,
-};
 
 //convert the royalty percentage and amount to pay into a payout (U128)
 pub(crate) fn royalty_to_payout(royalty_percentage: u32, amount_to_pay: Balance) -> U128 {
@@ -79,15 +64,20 @@ pub(crate) fn assert_at_least_one_yocto() {
 
 //refund the initial deposit based on the amount of storage that was used up
 pub(crate) fn refund_deposit(storage_used: u64) {
-    //get how much it would cost to store the information
-    let required_cost = env::storage_byte_cost() * Balance::from(storage_used);
+    refund_deposit_with_price(storage_used, 0)
+}
+
+//refund the initial deposit based on the amount of storage that was used up and the price that was charged on top of it
+pub(crate) fn refund_deposit_with_price(storage_used: u64, price: Balance) {
+    //get how much it would cost to store the information and add the price
+    let required_cost = env::storage_byte_cost() * Balance::from(storage_used) + price;
     //get the attached deposit
     let attached_deposit = env::attached_deposit();
 
     //make sure that the attached deposit is greater than or equal to the required cost
     assert!(
         required_cost <= attached_deposit,
-        "Must attach {} yoctoNEAR to cover storage",
+        "Must attach {} yoctoNEAR to cover price and storage",
         required_cost,
     );
 
@@ -100,27 +90,29 @@ pub(crate) fn refund_deposit(storage_used: u64) {
     }
 }
 
-pub(crate) fn aes_gcm_decrypt(password: &str, encrypted: &str) -> String {
-    let encrypted_b64 = base64::decode(encrypted.as_bytes()).expect("failed to decode b64");
-
-    let salt = SaltString::b64_encode(&encrypted_b64[0..16]).unwrap();
-    let nonce = GenericArray::from_slice(&encrypted_b64[16..28]);
-    let ciphertext = &encrypted_b64[28..];
-
-    let password_hash = Pbkdf2.hash_password_customized(
-        password.as_bytes(),
-        Some(Algorithm::Pbkdf2Sha256.ident()),
-        None,
-        Params {
-            rounds: 1,
-            output_length: 32,
-        },
-        &salt
-    ).unwrap();
-    let cipher = Aes256Gcm::new(GenericArray::from_slice(&password_hash.hash.unwrap().as_bytes()));
-    let payload = Payload { msg: ciphertext, aad: b"", };
-    let plaintext = cipher.decrypt(nonce, payload).unwrap();
-    str::from_utf8(&plaintext).unwrap().to_string()
+//remove up to `budget` entries of the map, decreasing the budget by the number of removed entries
+pub(crate) fn drain_unordered_map<K, V>(map: &mut UnorderedMap<K, V>, budget: &mut u32)
+where
+    K: BorshSerialize + BorshDeserialize,
+    V: BorshSerialize + BorshDeserialize,
+{
+    let keys: Vec<K> = map.keys().take(*budget as usize).collect();
+    for key in keys {
+        map.remove(&key);
+        *budget -= 1;
+    }
+}
+
+//remove up to `budget` elements of the set, decreasing the budget by the number of removed elements
+pub(crate) fn drain_unordered_set<T>(set: &mut UnorderedSet<T>, budget: &mut u32)
+where
+    T: BorshSerialize + BorshDeserialize,
+{
+    let elements: Vec<T> = set.iter().take(*budget as usize).collect();
+    for element in elements {
+        set.remove(&element);
+        *budget -= 1;
+    }
 }
 
 impl Contract {
@@ -135,6 +127,17 @@ impl Contract {
         );
     }
 
+    //make sure the sender is the contract owner or was granted the role
+    pub(crate) fn assert_has_role(&self, role: Role) {
+        let sender_id = env::predecessor_account_id();
+
+        assert!(
+            sender_id == self.owner_id || self.roles.contains(&(role, sender_id)),
+            "Requires the owner or the {:?} role",
+            role
+        );
+    }
+
     //add a token to the set of tokens an owner has
     pub(crate) fn internal_add_token_to_owner(
         &mut self,
@@ -196,6 +199,8 @@ impl Contract {
         approval_id: Option<u64>,
         memo: Option<String>,
     ) -> Token {
+        assert!(!self.pause_flags.transfer, "Transfers are paused");
+
         //get the token object by passing in the token_id
         let token = self.tokens_by_id.get(token_id).expect("No token");
 
@@ -224,6 +229,12 @@ impl Contract {
 			}
 		}
 
+        //characters inside a location can't change hands, unless they are forced to leave on transfer
+        if let Some(location_id) = self.character_locations.get(token_id) {
+            assert!(self.force_leave_on_transfer, "Character is locked in {}", location_id);
+            self.internal_force_leave(token_id);
+        }
+
         //we make sure that the sender isn't sending the token to themselves
         assert_ne!(
             &token.owner_id, receiver_id,
@@ -284,4 +295,27 @@ impl Contract {
         //return the previous token object that was transferred.
         token
     }
-} 
\ No newline at end of file
+
+    //remove up to `budget` tokens together with their metadata, locations and their entries in the sets of their owners
+    pub(crate) fn internal_drop_tokens(&mut self, budget: &mut u32) {
+        let token_ids: Vec<TokenId> = self.tokens_by_id.keys().take(*budget as usize).collect();
+        for token_id in token_ids {
+            let token = self.tokens_by_id.remove(&token_id).unwrap();
+            self.internal_remove_token_from_owner(&token.owner_id, &token_id);
+            self.token_metadata_by_id.remove(&token_id);
+            self.character_stats.remove(&token_id);
+            self.internal_leave_location(&token_id);
+            *budget -= 1;
+        }
+    }
+
+    //shrink the pool of unminted token ids from its end, the same way minting does, so that no position is left behind
+    pub(crate) fn internal_drop_unminted_token_ids(&mut self, budget: &mut u32) {
+        while *budget > 0 && self.minted_count < self.collection_size {
+            let last = self.collection_size - self.minted_count - 1;
+            self.unminted_token_ids.remove(&last);
+            self.minted_count += 1;
+            *budget -= 1;
+        }
+    }
+}
@@ -15,7 +15,14 @@ pub use crate::approval::*;
 pub use crate::royalty::*;
 pub use crate::events::*;
 pub use crate::reveal::*;
+pub use crate::sale::*;
+pub use crate::merkle::*;
+pub use crate::crypto::*;
+pub use crate::access::*;
+pub use crate::pause::*;
+pub use crate::migration::*;
 pub use crate::pray::location::*;
+pub use crate::pray::stats::*;
 
 mod internal;
 mod approval;
@@ -26,18 +33,34 @@ mod nft_core;
 mod royalty;
 mod events;
 mod reveal;
+mod sale;
+mod merkle;
+mod crypto;
+mod burn;
+mod access;
+mod pause;
+mod migration;
 mod pray;
 
 /// This spec can be treated like a version of the standard.
 pub const NFT_METADATA_SPEC: &str = "nft-1.0.0";
 /// This is the name of the NFT standard we're using
 pub const NFT_STANDARD_NAME: &str = "nep171";
+/// This is the name and version of the events specific to the Pray contracts
+pub const PRAY_STANDARD_NAME: &str = "pray";
+pub const PRAY_EVENTS_VERSION: &str = "1.0.0";
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct Contract {
     //contract owner
     pub owner_id: AccountId,
+    //account proposed as the new owner, which has to accept the ownership
+    pub pending_owner_id: Option<AccountId>,
+    //admin roles granted by the owner
+    pub roles: UnorderedSet<(Role, AccountId)>,
+    //features paused by the owner
+    pub pause_flags: PauseFlags,
 
     //keeps track of all the token IDs for a given account
     pub tokens_per_owner: LookupMap<AccountId, UnorderedSet<TokenId>>,
@@ -55,8 +78,59 @@ pub struct Contract {
     pub royalty: HashMap<AccountId, u32>,
 
     pub collection_size: u32,
+    //number of tokens minted so far, burned tokens included
+    pub minted_count: u32,
+    /*
+        pool of token ids which weren't minted yet, stored as a sparse Fisher-Yates shuffle.
+        position i of the pool holds the token id stored under i, or i itself if nothing is stored.
+    */
+    pub unminted_token_ids: LookupMap<u32, u32>,
     pub collection_state: CollectionState,
     pub encrypted_metadata: Vector<String>,
+    //salt and key derivation rounds shared by every encrypted metadata batch
+    pub kdf_params: Option<KdfParams>,
+    //number of encrypted metadata batches revealed so far
+    pub revealed_batches: u64,
+    //sha256 hashes of the decrypted metadata batches, committed to before the collection is published
+    pub provenance_batch_hashes: Vec<CryptoHash>,
+    //sha256 hash of the key the metadata is encrypted with, committed to before the collection is published
+    pub reveal_key_hash: Option<CryptoHash>,
+    //AES key the metadata is encrypted with, derived from the reveal key submitted when it's time to reveal
+    pub reveal_key: Option<DerivedKey>,
+    //whether legacy metadata payloads encrypted with a single round key derivation can be revealed
+    pub allow_legacy_kdf: bool,
+    /*
+        number of consecutive token ids per metadata batch when metadata is revealed lazily:
+        batch i holds the tokens i * size to (i + 1) * size - 1 and views decrypt it on demand
+    */
+    pub lazy_reveal_batch_size: Option<u32>,
+
+    //price of a single token (storage is charged on top of it)
+    pub mint_price: Balance,
+    //sale proceeds collected by nft_mint which weren't withdrawn by the owner yet
+    pub proceeds: Balance,
+
+    //sale schedule sorted by start time. If empty, minting is public at the mint price
+    pub sale_phases: Vec<SalePhase>,
+    //accounts allowed to mint during a phase, keyed by phase name
+    pub sale_allowlist: UnorderedSet<(String, AccountId)>,
+    //number of tokens minted by an account during a phase, keyed by phase name
+    pub sale_minted_per_account: UnorderedMap<(String, AccountId), u32>,
+    //merkle root of the (account, quota) allowlist entries accepted during allowlist phases
+    pub allowlist_root: Option<CryptoHash>,
+    //number of tokens minted by an account against its merkle allowlist quota
+    pub allowlist_minted: UnorderedMap<AccountId, u32>,
+
+    //registry of the location contracts which can move characters
+    pub locations: UnorderedMap<AccountId, LocationMetadata>,
+    //location contract each character is currently in
+    pub character_locations: LookupMap<TokenId, AccountId>,
+    //number of characters in each location
+    pub location_populations: LookupMap<AccountId, u32>,
+    //whether transferring a character inside a location makes it leave instead of being refused
+    pub force_leave_on_transfer: bool,
+    //stats of the revealed characters
+    pub character_stats: UnorderedMap<TokenId, CharacterStats>,
 }
 
 /// Helper structure for keys of the persistent collections.
@@ -71,6 +145,15 @@ pub enum StorageKey {
     TokensPerType,
     TokensPerTypeInner { token_type_hash: CryptoHash },
     TokenTypesLocked,
+    SaleAllowlist,
+    SaleMintedPerAccount,
+    AllowlistMinted,
+    UnmintedTokenIds,
+    Roles,
+    Locations,
+    CharacterLocations,
+    CharacterStats,
+    LocationPopulations,
 }
 
 #[derive(Debug, PartialEq, PartialOrd, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
@@ -91,7 +174,9 @@ impl Contract {
     pub fn new_default_meta(
         owner_id: AccountId,
         collection_size: u32,
-        perpetual_royalties: Option<HashMap<AccountId, u32>>
+        mint_price: U128,
+        perpetual_royalties: Option<HashMap<AccountId, u32>>,
+        locations: Option<Vec<JsonLocation>>
     ) -> Self {
         //calls the other function "new: with some default metadata and the owner_id passed in 
         Self::new(
@@ -107,6 +192,8 @@ impl Contract {
             },
             perpetual_royalties,
             collection_size,
+            mint_price,
+            locations,
         )
     }
 
@@ -120,7 +207,10 @@ impl Contract {
         owner_id: AccountId,
         metadata: NFTContractMetadata,
         perpetual_royalties: Option<HashMap<AccountId, u32>>,
-        collection_size: u32
+        collection_size: u32,
+        mint_price: U128,
+        //location contracts which can move characters
+        locations: Option<Vec<JsonLocation>>
     ) -> Self {
         // create a royalty map to store in the token
         let mut royalty = HashMap::new();
@@ -137,7 +227,7 @@ impl Contract {
         }
 
         //create a variable of type Self with all the fields initialized. 
-        let this = Self {
+        let mut this = Self {
             //Storage keys are simply the prefixes used for the collections. This helps avoid data collision
             tokens_per_owner: LookupMap::new(StorageKey::TokensPerOwner.try_to_vec().unwrap()),
             tokens_by_id: UnorderedMap::new(StorageKey::TokensById.try_to_vec().unwrap()),
@@ -146,34 +236,115 @@ impl Contract {
             ),
             //set the owner_id field equal to the passed in owner_id. 
             owner_id,
+            pending_owner_id: None,
+            roles: UnorderedSet::new(StorageKey::Roles.try_to_vec().unwrap()),
+            pause_flags: PauseFlags::default(),
             metadata: LazyOption::new(
                 StorageKey::NFTContractMetadata.try_to_vec().unwrap(),
                 Some(&metadata),
             ),
             royalty: royalty,
             collection_size: collection_size,
+            minted_count: 0,
+            unminted_token_ids: LookupMap::new(StorageKey::UnmintedTokenIds.try_to_vec().unwrap()),
             collection_state: CollectionState::Deployed,
             encrypted_metadata: Vector::new(StorageKey::EncryptedMetadata.try_to_vec().unwrap()),
+            kdf_params: None,
+            revealed_batches: 0,
+            provenance_batch_hashes: Vec::new(),
+            reveal_key_hash: None,
+            reveal_key: None,
+            allow_legacy_kdf: false,
+            lazy_reveal_batch_size: None,
+            mint_price: mint_price.into(),
+            proceeds: 0,
+            sale_phases: Vec::new(),
+            sale_allowlist: UnorderedSet::new(StorageKey::SaleAllowlist.try_to_vec().unwrap()),
+            sale_minted_per_account: UnorderedMap::new(StorageKey::SaleMintedPerAccount.try_to_vec().unwrap()),
+            allowlist_root: None,
+            allowlist_minted: UnorderedMap::new(StorageKey::AllowlistMinted.try_to_vec().unwrap()),
+            locations: UnorderedMap::new(StorageKey::Locations.try_to_vec().unwrap()),
+            character_locations: LookupMap::new(StorageKey::CharacterLocations.try_to_vec().unwrap()),
+            location_populations: LookupMap::new(StorageKey::LocationPopulations.try_to_vec().unwrap()),
+            force_leave_on_transfer: false,
+            character_stats: UnorderedMap::new(StorageKey::CharacterStats.try_to_vec().unwrap()),
         };
+        for location in locations.unwrap_or_default() {
+            this.locations.insert(&location.location_id, &location.metadata);
+        }
+        write_state_version();
 
         //return the Contract object
         this
     }
 
     pub fn set_collection_state(&mut self, collection_state: CollectionState) {
-        self.assert_called_by_owner();
+        //publishing ends the metadata upload and marking the collection as revealed ends the reveal
+        match collection_state {
+            CollectionState::Revealed => self.assert_has_role(Role::Revealer),
+            _ => self.assert_has_role(Role::MetadataManager),
+        }
         assert!(collection_state > self.collection_state, "Illegal state");
+        if collection_state == CollectionState::Published && !self.encrypted_metadata.is_empty() {
+            assert_eq!(
+                self.provenance_batch_hashes.len() as u64,
+                self.encrypted_metadata.len(),
+                "Provenance must be committed for every metadata batch before the collection is published"
+            );
+            assert!(
+                self.reveal_key_hash.is_some(),
+                "Reveal key must be committed before the collection is published"
+            );
+            if let Some(batch_size) = self.lazy_reveal_batch_size {
+                let expected_batches = self.collection_size.saturating_sub(1) / batch_size + 1;
+                assert_eq!(
+                    self.encrypted_metadata.len(),
+                    u64::from(expected_batches),
+                    "Lazy reveal expects {} metadata batches of {} tokens",
+                    expected_batches,
+                    batch_size
+                );
+            }
+        }
 
         self.collection_state = collection_state;
     }
 
-    pub fn drop_state(&mut self) {
+    /*
+        tear the state down before the account is deleted, removing every collection of the contract.
+        every call removes up to `limit` entries and returns true while there is something left, so it is called until it returns false.
+        the state of a published collection can only be dropped if `force` is passed.
+    */
+    pub fn drop_state(&mut self, limit: Option<u32>, force: Option<bool>) -> bool {
         self.assert_called_by_owner();
-        // assert!(self.collection_state < CollectionState::Published, "Can't drop the state of a published collection");
+        assert!(
+            self.collection_state < CollectionState::Published || force.unwrap_or(false),
+            "Can't drop the state of a published collection without force"
+        );
+
+        let mut budget = limit.unwrap_or(50);
+        assert!(budget > 0, "Limit must be positive");
+
+        self.internal_drop_tokens(&mut budget);
+        //metadata revealed for tokens which were never minted
+        drain_unordered_map(&mut self.token_metadata_by_id, &mut budget);
+        drain_unordered_map(&mut self.character_stats, &mut budget);
+        while budget > 0 && self.encrypted_metadata.pop().is_some() {
+            budget -= 1;
+        }
+        self.internal_drop_unminted_token_ids(&mut budget);
+        drain_unordered_set(&mut self.sale_allowlist, &mut budget);
+        drain_unordered_map(&mut self.sale_minted_per_account, &mut budget);
+        drain_unordered_map(&mut self.allowlist_minted, &mut budget);
+        drain_unordered_set(&mut self.roles, &mut budget);
+        drain_unordered_map(&mut self.locations, &mut budget);
+        if budget == 0 {
+            return true;
+        }
 
-        self.tokens_by_id.clear();
-        self.token_metadata_by_id.clear();
-        self.encrypted_metadata.clear();
+        self.metadata.remove();
+        self.revealed_batches = 0;
+        false
     }
 }
 
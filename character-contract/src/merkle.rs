@@ -0,0 +1,84 @@
+use crate::*;
+
+//proof that an account is on the allowlist committed to by the merkle root
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AllowlistProof {
+    //how many tokens the account can mint during allowlist phases
+    pub quota: u32,
+    //sibling hashes from the leaf up to the root
+    pub proof: Vec<Base64VecU8>,
+}
+
+//hash of a single allowlist entry
+pub fn allowlist_leaf(account_id: &AccountId, quota: u32) -> CryptoHash {
+    to_crypto_hash(&env::sha256(format!("{}:{}", account_id, quota).as_bytes()))
+}
+
+//hash two nodes together. Nodes are sorted first so that proofs don't have to carry the side of each sibling
+pub fn merkle_hash_pair(a: &CryptoHash, b: &CryptoHash) -> CryptoHash {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    to_crypto_hash(&env::sha256(&[left.as_ref(), right.as_ref()].concat()))
+}
+
+//check that the leaf is part of the tree with the given root
+pub fn verify_merkle_proof(leaf: CryptoHash, proof: &[CryptoHash], root: &CryptoHash) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| merkle_hash_pair(&node, sibling));
+    &computed == root
+}
+
+pub(crate) fn to_crypto_hash(bytes: &[u8]) -> CryptoHash {
+    assert_eq!(bytes.len(), 32, "Hash must be 32 bytes long");
+    let mut hash = CryptoHash::default();
+    hash.copy_from_slice(bytes);
+    hash
+}
+
+/*
+    merkle tree built off-chain from the allowlist entries.
+    the owner uploads the root and every allowlisted account gets the proof of its own leaf.
+*/
+pub struct MerkleTree {
+    //levels[0] holds the leaves, the last level holds the root
+    levels: Vec<Vec<CryptoHash>>,
+}
+
+impl MerkleTree {
+    pub fn new(leaves: Vec<CryptoHash>) -> Self {
+        assert!(!leaves.is_empty(), "Merkle tree needs at least one leaf");
+
+        let mut levels = vec![leaves];
+        while levels.last().unwrap().len() > 1 {
+            let level = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => merkle_hash_pair(left, right),
+                    //a node without a sibling is carried over to the next level as is
+                    [node] => *node,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(level);
+        }
+
+        Self { levels }
+    }
+
+    pub fn root(&self) -> CryptoHash {
+        self.levels.last().unwrap()[0]
+    }
+
+    //sibling hashes of the leaf at the given index, from the bottom of the tree up
+    pub fn proof(&self, mut index: usize) -> Vec<CryptoHash> {
+        let mut proof = Vec::new();
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = level.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+        proof
+    }
+}
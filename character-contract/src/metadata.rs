@@ -58,6 +58,8 @@ pub struct JsonToken {
     pub metadata: TokenMetadata,
     //list of approved account IDs that have access to transfer the token. This maps an account ID to an approval ID
     pub approved_account_ids: HashMap<AccountId, u64>,
+    //location contract the character is currently in
+    pub location: Option<AccountId>,
 }
 
 pub trait NonFungibleTokenMetadata {
@@ -0,0 +1,168 @@
+use crate::*;
+
+/*
+    version of the state layout written by this code.
+    version 2 is the final layout of the changes made since version 1, from roles and pausing to locations and lazy reveal.
+    they were deployed together, so the intermediate layouts have no version, and the `fixtures` of the tests freeze both versions.
+    from now on, whenever the layout of `Contract` changes, bump it, freeze the previous layout below, add an arm to `migrate`
+    and a fixture of the new layout.
+*/
+pub const STATE_VERSION: u32 = 2;
+//the version is kept under its own key, so that it can be read before the state is deserialized
+const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+//the location contract of version 1 migrates at most this many present characters, which are given to the migration from version 1
+pub const MAX_MIGRATED_CHARACTERS: usize = 100;
+
+//layout of the state before it was versioned, when tokens were minted sequentially and revealed with a password
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ContractV1 {
+    pub owner_id: AccountId,
+    pub tokens_per_owner: LookupMap<AccountId, UnorderedSet<TokenId>>,
+    pub tokens_by_id: UnorderedMap<TokenId, Token>,
+    pub token_metadata_by_id: UnorderedMap<TokenId, TokenMetadata>,
+    pub metadata: LazyOption<NFTContractMetadata>,
+    pub royalty: HashMap<AccountId, u32>,
+    pub collection_size: u32,
+    pub collection_state: CollectionState,
+    pub encrypted_metadata: Vector<String>,
+}
+
+//read the version of the state in the storage. State written before it was versioned has no version
+pub(crate) fn read_state_version() -> u32 {
+    env::storage_read(STATE_VERSION_KEY)
+        .map(|version| u32::try_from_slice(&version).expect("Invalid state version"))
+        .unwrap_or(1)
+}
+
+pub(crate) fn write_state_version() {
+    env::storage_write(STATE_VERSION_KEY, &STATE_VERSION.try_to_vec().unwrap());
+}
+
+#[near_bindgen]
+impl Contract {
+    /*
+        upgrade the state written by a previous version of the contract to the current layout.
+        call it right after the new code is deployed, e.g. with `near deploy --initFunction migrate`.
+        version 1 moved characters into a hardcoded location, so the locations and the characters present in each of them,
+        the same ones given to the migration of the locations, have to be given. A collection which was published
+        with metadata left to reveal also needs the provenance and the reveal key commitment of `set_provenance` and `commit_reveal_key`.
+    */
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate(
+        locations: Option<Vec<JsonLocation>>,
+        present_characters: Option<HashMap<AccountId, Vec<TokenId>>>,
+        batch_hashes: Option<Vec<Base64VecU8>>,
+        key_hash: Option<Base64VecU8>,
+    ) -> Self {
+        let contract = match read_state_version() {
+            1 => Self::migrate_from_v1(
+                env::state_read().expect("No state to migrate"),
+                locations.expect("The locations are required to migrate from version 1"),
+                present_characters.expect("The characters present in the locations are required to migrate from version 1"),
+                batch_hashes,
+                key_hash,
+            ),
+            version => panic!("State version {} can't be migrated to version {}", version, STATE_VERSION),
+        };
+        write_state_version();
+
+        contract
+    }
+
+    //view the version of the state layout
+    pub fn state_version(&self) -> u32 {
+        read_state_version()
+    }
+}
+
+impl Contract {
+    fn migrate_from_v1(
+        old: ContractV1,
+        locations: Vec<JsonLocation>,
+        present_characters: HashMap<AccountId, Vec<TokenId>>,
+        batch_hashes: Option<Vec<Base64VecU8>>,
+        key_hash: Option<Base64VecU8>,
+    ) -> Self {
+        //tokens were minted sequentially, so the ids below the number of minted tokens are taken
+        let minted_count = old.tokens_by_id.len() as u32;
+        let kdf_params = old.encrypted_metadata.get(0).and_then(|batch| kdf_params(&batch).ok());
+
+        let mut this = Self {
+            owner_id: old.owner_id,
+            pending_owner_id: None,
+            roles: UnorderedSet::new(StorageKey::Roles.try_to_vec().unwrap()),
+            pause_flags: PauseFlags::default(),
+            tokens_per_owner: old.tokens_per_owner,
+            tokens_by_id: old.tokens_by_id,
+            token_metadata_by_id: old.token_metadata_by_id,
+            metadata: old.metadata,
+            royalty: old.royalty,
+            collection_size: old.collection_size,
+            minted_count,
+            unminted_token_ids: LookupMap::new(StorageKey::UnmintedTokenIds.try_to_vec().unwrap()),
+            collection_state: old.collection_state,
+            //revealed batches used to be popped, so the batches left in the state weren't revealed yet
+            encrypted_metadata: old.encrypted_metadata,
+            kdf_params,
+            revealed_batches: 0,
+            provenance_batch_hashes: batch_hashes.unwrap_or_default().iter().map(|hash| to_crypto_hash(&hash.0)).collect(),
+            reveal_key_hash: key_hash.map(|key_hash| to_crypto_hash(&key_hash.0)),
+            reveal_key: None,
+            //metadata used to be encrypted with a single round key derivation
+            allow_legacy_kdf: true,
+            lazy_reveal_batch_size: None,
+            //minting used to charge the storage only
+            mint_price: 0,
+            proceeds: 0,
+            sale_phases: Vec::new(),
+            sale_allowlist: UnorderedSet::new(StorageKey::SaleAllowlist.try_to_vec().unwrap()),
+            sale_minted_per_account: UnorderedMap::new(StorageKey::SaleMintedPerAccount.try_to_vec().unwrap()),
+            allowlist_root: None,
+            allowlist_minted: UnorderedMap::new(StorageKey::AllowlistMinted.try_to_vec().unwrap()),
+            locations: UnorderedMap::new(StorageKey::Locations.try_to_vec().unwrap()),
+            character_locations: LookupMap::new(StorageKey::CharacterLocations.try_to_vec().unwrap()),
+            location_populations: LookupMap::new(StorageKey::LocationPopulations.try_to_vec().unwrap()),
+            force_leave_on_transfer: false,
+            character_stats: UnorderedMap::new(StorageKey::CharacterStats.try_to_vec().unwrap()),
+        };
+        //a published collection can't be given them anymore, so the batches left to reveal need both from the migration
+        if this.collection_state >= CollectionState::Published && !this.encrypted_metadata.is_empty() {
+            assert!(
+                !this.provenance_batch_hashes.is_empty() && this.reveal_key_hash.is_some(),
+                "The provenance and the reveal key commitment of the metadata left to reveal are required to migrate a published collection"
+            );
+        }
+
+        //the location contract used to be hardcoded, and its characters were only known to it
+        for location in locations {
+            this.locations.insert(&location.location_id, &location.metadata);
+        }
+        assert!(
+            present_characters.values().map(|character_ids| character_ids.len()).sum::<usize>() <= MAX_MIGRATED_CHARACTERS,
+            "At most {} present characters can be migrated",
+            MAX_MIGRATED_CHARACTERS
+        );
+        for (location_id, character_ids) in present_characters {
+            assert!(this.locations.get(&location_id).is_some(), "Location {} isn't registered", location_id);
+            for character_id in character_ids {
+                assert!(this.tokens_by_id.get(&character_id).is_some(), "No token {}", character_id);
+                if let Some(current_location) = this.character_locations.get(&character_id) {
+                    panic!("Character {} is already in {}", character_id, current_location);
+                }
+                this.internal_enter_location(&character_id, &location_id);
+            }
+        }
+
+        /*
+            the pool of unminted ids holds the ids 0..remaining by default. Move the ids which are still unminted
+            but beyond the pool into the positions of the minted ids, so that the pool holds exactly minted_count..collection_size
+        */
+        let remaining = this.collection_size - minted_count;
+        for position in 0..minted_count.min(remaining) {
+            this.unminted_token_ids.insert(&position, &(minted_count.max(remaining) + position));
+        }
+
+        this
+    }
+}
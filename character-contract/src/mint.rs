@@ -6,33 +6,103 @@ impl Contract {
     pub fn nft_mint(
         &mut self,
         receiver_id: AccountId,
-    ) {
+        //proof of the minter's merkle allowlist entry, used during allowlist phases
+        allowlist_proof: Option<AllowlistProof>,
+    ) -> TokenId {
+        self.internal_mint(receiver_id, 1, allowlist_proof).remove(0)
+    }
+
+    //mint several tokens in one transaction. Storage for all of them is charged together
+    #[payable]
+    pub fn nft_batch_mint(
+        &mut self,
+        receiver_id: AccountId,
+        count: u32,
+        //proof of the minter's merkle allowlist entry, used during allowlist phases
+        allowlist_proof: Option<AllowlistProof>,
+    ) -> Vec<TokenId> {
+        self.internal_mint(receiver_id, count, allowlist_proof)
+    }
+
+    pub fn set_mint_price(&mut self, mint_price: U128) {
+        self.assert_has_role(Role::SaleManager);
+
+        self.mint_price = mint_price.into();
+    }
+
+    //withdraw the sale proceeds to the owner. If no amount is passed, everything collected so far is withdrawn
+    pub fn withdraw_proceeds(&mut self, amount: Option<U128>) -> Promise {
+        self.assert_has_role(Role::Treasurer);
+
+        let amount = amount.map(u128::from).unwrap_or(self.proceeds);
+        assert!(amount > 0, "Nothing to withdraw");
+        assert!(amount <= self.proceeds, "Can't withdraw more than {} yoctoNEAR", self.proceeds);
+
+        self.proceeds -= amount;
+        Promise::new(self.owner_id.clone()).transfer(amount)
+    }
+
+    //view the price of a single token
+    pub fn mint_price(&self) -> U128 {
+        U128(self.mint_price)
+    }
+
+    //view the sale proceeds which weren't withdrawn yet
+    pub fn mint_proceeds(&self) -> U128 {
+        U128(self.proceeds)
+    }
+}
+
+impl Contract {
+    //mint `count` tokens to the receiver, charging the price of the active sale phase and the storage
+    pub(crate) fn internal_mint(
+        &mut self,
+        receiver_id: AccountId,
+        count: u32,
+        allowlist_proof: Option<AllowlistProof>,
+    ) -> Vec<TokenId> {
+        assert!(!self.pause_flags.mint, "Minting is paused");
         assert!(self.collection_state >= CollectionState::Published, "Minting is not allowed before the collection is published");
-        assert!((self.tokens_by_id.len() as u32) < self.collection_size, "Entire collection was already minted");
+        assert!(count > 0, "Must mint at least one token");
+        assert!(self.minted_count < self.collection_size, "Entire collection was already minted");
+        assert!(
+            self.minted_count + count <= self.collection_size,
+            "Only {} tokens are left in the collection",
+            self.collection_size - self.minted_count
+        );
 
-        let token_id: TokenId = self.tokens_by_id.len().to_string();
+        //make sure the active sale phase allows the minter to mint and get the price of a single token
+        let price = self.internal_use_sale_quota(&env::predecessor_account_id(), count, allowlist_proof);
 
         //measure the initial storage being used on the contract
         let initial_storage_usage = env::storage_usage();
 
-        //specify the token struct that contains the owner ID 
-        let token = Token {
-            //set the owner ID equal to the receiver ID passed into the function
-            owner_id: receiver_id,
-            //we set the approved account IDs to the default value (an empty map)
-            approved_account_ids: Default::default(),
-            //the next approval ID is set to 0
-            next_approval_id: 0,
-        };
+        let mut token_ids = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            //draw a random token id from the ones which weren't minted yet
+            let token_id: TokenId = self.internal_draw_token_id().to_string();
 
-        //insert the token ID and token struct and make sure that the token doesn't exist
-        assert!(
-            self.tokens_by_id.insert(&token_id, &token).is_none(),
-            "Token already exists"
-        );
+            //specify the token struct that contains the owner ID 
+            let token = Token {
+                //set the owner ID equal to the receiver ID passed into the function
+                owner_id: receiver_id.clone(),
+                //we set the approved account IDs to the default value (an empty map)
+                approved_account_ids: Default::default(),
+                //the next approval ID is set to 0
+                next_approval_id: 0,
+            };
+
+            //insert the token ID and token struct and make sure that the token doesn't exist
+            assert!(
+                self.tokens_by_id.insert(&token_id, &token).is_none(),
+                "Token already exists"
+            );
 
-        //call the internal method for adding the token to the owner
-        self.internal_add_token_to_owner(&token.owner_id, &token_id);
+            //call the internal method for adding the token to the owner
+            self.internal_add_token_to_owner(&token.owner_id, &token_id);
+
+            token_ids.push(token_id);
+        }
 
         // Construct the mint log as per the events standard.
         let nft_mint_log: EventLog = EventLog {
@@ -42,10 +112,10 @@ impl Contract {
             version: NFT_METADATA_SPEC.to_string(),
             // The data related with the event stored in a vector.
             event: EventLogVariant::NftMint(vec![NftMintLog {
-                // Owner of the token.
-                owner_id: token.owner_id.to_string(),
+                // Owner of the tokens.
+                owner_id: receiver_id.to_string(),
                 // Vector of token IDs that were minted.
-                token_ids: vec![token_id.to_string()],
+                token_ids: token_ids.clone(),
                 // An optional memo to include.
                 memo: None,
             }]),
@@ -57,7 +127,36 @@ impl Contract {
         //calculate the required storage which was the used - initial
         let required_storage_in_bytes = env::storage_usage() - initial_storage_usage;
 
-        //refund any excess storage if the user attached too much. Panic if they didn't attach enough to cover the required.
-        refund_deposit(required_storage_in_bytes);
+        //refund any excess deposit if the user attached too much. Panic if they didn't attach enough to cover the price and storage.
+        let total_price = price * Balance::from(count);
+        refund_deposit_with_price(required_storage_in_bytes, total_price);
+
+        //keep track of the sale proceeds so that the owner can withdraw them
+        self.proceeds += total_price;
+
+        token_ids
+    }
+
+    //remove a random token id from the pool of unminted ones and return it
+    pub(crate) fn internal_draw_token_id(&mut self) -> u32 {
+        let remaining = self.collection_size - self.minted_count;
+        assert!(remaining > 0, "Entire collection was already minted");
+
+        //mix the block's random seed with the mint counter, so that every token of a batch gets a different draw
+        let seed = [env::random_seed(), self.minted_count.to_le_bytes().to_vec()].concat();
+        let mut random_bytes = [0u8; 8];
+        random_bytes.copy_from_slice(&env::sha256(&seed)[..8]);
+        let index = (u64::from_le_bytes(random_bytes) % u64::from(remaining)) as u32;
+
+        //swap the drawn position with the last position of the pool and shrink the pool by one
+        let last = remaining - 1;
+        let token_id = self.unminted_token_ids.get(&index).unwrap_or(index);
+        let last_token_id = self.unminted_token_ids.remove(&last).unwrap_or(last);
+        if index != last {
+            self.unminted_token_ids.insert(&index, &last_token_id);
+        }
+        self.minted_count += 1;
+
+        token_id
     }
-}
\ No newline at end of file
+}
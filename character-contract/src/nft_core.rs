@@ -160,11 +160,24 @@ impl NonFungibleTokenCore for Contract {
 
     //get the information for a specific token ID
     fn nft_token(&self, token_id: TokenId) -> Option<JsonToken> {
+        self.internal_json_token(token_id, &mut HashMap::new())
+    }
+}
+
+impl Contract {
+    //get the JsonToken of a token, decrypting its metadata on demand if it is revealed lazily
+    pub(crate) fn internal_json_token(
+        &self,
+        token_id: TokenId,
+        decrypted_batches: &mut HashMap<u64, Option<HashMap<TokenId, TokenMetadata>>>,
+    ) -> Option<JsonToken> {
         //if there is some token ID in the tokens_by_id collection
         if let Some(token) = self.tokens_by_id.get(&token_id) {
             //we'll get the metadata for that token
             let metadata = if let Some(metadata) = self.token_metadata_by_id.get(&token_id) {
                 metadata
+            } else if let Some(metadata) = self.internal_lazy_token_metadata(&token_id, decrypted_batches) {
+                metadata
             } else {
                 TokenMetadata {
                     title: Some("Mistery".to_string()),
@@ -183,11 +196,13 @@ impl NonFungibleTokenCore for Contract {
             };
 
             //we return the JsonToken (wrapped by Some since we return an option)
+            let location = self.character_locations.get(&token_id);
             Some(JsonToken {
                 token_id,
                 owner_id: token.owner_id,
                 metadata: metadata,
                 approved_account_ids: token.approved_account_ids,
+                location,
             })
         } else { //if there wasn't a token ID in the tokens_by_id collection, we return None
             None
@@ -0,0 +1,40 @@
+use crate::*;
+
+//features the owner can pause, e.g. when an exploit is found
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PauseFlags {
+    //nft_mint and nft_batch_mint
+    pub mint: bool,
+    //every token transfer, including the transfers of nft_transfer_call and nft_transfer_payout, and burning tokens
+    pub transfer: bool,
+    pub approve: bool,
+    //characters entering and leaving locations
+    pub move_character: bool,
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn set_pause_flags(&mut self, pause_flags: PauseFlags) {
+        self.assert_called_by_owner();
+
+        self.pause_flags = pause_flags.clone();
+
+        // Construct the pause log.
+        let pause_log: EventLog = EventLog {
+            // Standard name ("pray").
+            standard: PRAY_STANDARD_NAME.to_string(),
+            // Version of the standard ("1.0.0").
+            version: PRAY_EVENTS_VERSION.to_string(),
+            // The data related with the event stored in a vector.
+            event: EventLogVariant::PauseFlagsUpdate(vec![pause_flags]),
+        };
+
+        // Log the serialized json.
+        env::log_str(&pause_log.to_string());
+    }
+
+    pub fn pause_flags(&self) -> PauseFlags {
+        self.pause_flags.clone()
+    }
+}
@@ -1,23 +1,203 @@
 use crate::*;
+use near_sdk::{ext_contract, Gas};
+
+const GAS_FOR_ON_FORCE_LEAVE: Gas = Gas(10_000_000_000_000);
+
+#[ext_contract(ext_location)]
+trait LocationContract {
+    //settles what the character accrued and removes it from the location contract
+    fn on_force_leave(&mut self, character_id: TokenId);
+}
+
+//position of a location on the world map
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Coordinates {
+    pub x: i32,
+    pub y: i32,
+}
+
+//metadata of a location contract registered on the character contract
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LocationMetadata {
+    pub name: String,
+    pub coordinates: Coordinates,
+    //characters can't enter disabled locations, but can still leave them
+    pub enabled: bool,
+}
+
+//the location as it is passed into init and returned by the views
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonLocation {
+    pub location_id: AccountId,
+    pub metadata: LocationMetadata,
+}
+
+//why a token can't be transferred
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "reason")]
+pub enum TransferLock {
+    TransfersPaused,
+    //the character is inside a location and isn't forced to leave on transfer
+    InLocation { location_id: AccountId },
+}
 
 #[near_bindgen]
 impl Contract {
     pub fn move_character(&mut self, character_id: TokenId, destination: Option<String>) -> bool {
         let caller_id: AccountId = env::predecessor_account_id();
-        assert_eq!(caller_id, AccountId::try_from("location.pray.devgenerate.testnet".to_string()).unwrap(), "Can only be called by location contract");
+        let location = self.locations.get(&caller_id);
+        if destination.is_some() {
+            let location = location.as_ref().expect("Can only be called by a registered location contract");
+            assert!(location.enabled, "Location is disabled");
+        }
+        assert!(!self.pause_flags.move_character, "Character moves are paused");
 
         let signer_id = env::signer_account_id();
         let token = self.tokens_by_id.get(&character_id).expect("No token");
         assert_eq!(signer_id, token.owner_id, "Character is not owned by the signer");
 
-        // check character's location
-
+        //a character is in one location at a time and can only leave the location it is in
+        let current_location = self.character_locations.get(&character_id);
+        let location_name = location.map(|location| location.name).unwrap_or_else(|| caller_id.to_string());
         let message = match destination {
-            Some(location) => format!("Character {} entered {}", character_id, location),
-            None => format!("Character {} left", character_id),
+            Some(_) => {
+                if let Some(current_location) = current_location {
+                    panic!("Character is already in {}", current_location);
+                }
+                self.internal_enter_location(&character_id, &caller_id);
+                format!("Character {} entered {}", character_id, location_name)
+            }
+            None => {
+                //the location the character is in can always make it leave, even once it is disabled
+                assert_eq!(current_location, Some(caller_id), "Character is not in this location");
+                self.internal_leave_location(&character_id);
+                format!("Character {} left {}", character_id, location_name)
+            }
         };
         env::log_str(&message);
-        
+
         true
     }
-}
\ No newline at end of file
+
+    //register a location contract which can move characters, or update the metadata of a registered one
+    pub fn register_location(&mut self, location_id: AccountId, metadata: LocationMetadata) {
+        self.assert_called_by_owner();
+
+        self.locations.insert(&location_id, &metadata);
+    }
+
+    //only an empty location can be unregistered, otherwise its characters would be stuck in it
+    pub fn unregister_location(&mut self, location_id: AccountId) {
+        self.assert_called_by_owner();
+        assert_eq!(
+            self.characters_in_location(location_id.clone()),
+            0,
+            "Characters are still in the location, they have to leave or be forced to leave first"
+        );
+
+        self.locations.remove(&location_id).expect("Location is not registered");
+    }
+
+    //enable or disable a registered location without losing its metadata
+    pub fn set_location_enabled(&mut self, location_id: AccountId, enabled: bool) {
+        self.assert_called_by_owner();
+
+        let mut metadata = self.locations.get(&location_id).expect("Location is not registered");
+        metadata.enabled = enabled;
+        self.locations.insert(&location_id, &metadata);
+    }
+
+    //view the number of characters in a location
+    pub fn characters_in_location(&self, location_id: AccountId) -> u32 {
+        self.location_populations.get(&location_id).unwrap_or(0)
+    }
+
+    //view the location contract the character is currently in
+    pub fn character_location(&self, token_id: TokenId) -> Option<AccountId> {
+        self.character_locations.get(&token_id)
+    }
+
+    //make transfers of characters inside a location force them to leave instead of being refused
+    pub fn set_force_leave_on_transfer(&mut self, force_leave_on_transfer: bool) {
+        self.assert_called_by_owner();
+
+        self.force_leave_on_transfer = force_leave_on_transfer;
+    }
+
+    pub fn force_leave_on_transfer(&self) -> bool {
+        self.force_leave_on_transfer
+    }
+
+    //make a character leave its location without the location contract, e.g. when the location is broken or disabled
+    pub fn force_leave(&mut self, character_id: TokenId) {
+        self.assert_called_by_owner();
+        assert!(self.character_locations.get(&character_id).is_some(), "Character is not in a location");
+
+        self.internal_force_leave(&character_id);
+    }
+
+    //view whether the token can't be transferred and why
+    pub fn transfer_lock(&self, token_id: TokenId) -> Option<TransferLock> {
+        if self.pause_flags.transfer {
+            return Some(TransferLock::TransfersPaused);
+        }
+        if self.force_leave_on_transfer {
+            return None;
+        }
+        self.character_locations
+            .get(&token_id)
+            .map(|location_id| TransferLock::InLocation { location_id })
+    }
+
+    //view the metadata of a registered location
+    pub fn location(&self, location_id: AccountId) -> Option<LocationMetadata> {
+        self.locations.get(&location_id)
+    }
+
+    //view the registered locations using pagination
+    pub fn locations(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<JsonLocation> {
+        //where to start pagination - if we have a from_index, we'll use that - otherwise start from 0 index
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+
+        self.locations
+            .iter()
+            .skip(start as usize)
+            //take the first "limit" elements. If we didn't specify a limit, use 50
+            .take(limit.unwrap_or(50) as usize)
+            .map(|(location_id, metadata)| JsonLocation { location_id, metadata })
+            .collect()
+    }
+}
+
+impl Contract {
+    //remove the character from its location without waiting for the location contract, which is only notified
+    pub(crate) fn internal_force_leave(&mut self, character_id: &TokenId) {
+        if let Some(location_id) = self.internal_leave_location(character_id) {
+            env::log_str(&format!("Character {} was forced to leave {}", character_id, location_id));
+            ext_location::ext(location_id)
+                .with_static_gas(GAS_FOR_ON_FORCE_LEAVE)
+                .on_force_leave(character_id.clone());
+        }
+    }
+
+    pub(crate) fn internal_enter_location(&mut self, character_id: &TokenId, location_id: &AccountId) {
+        self.character_locations.insert(character_id, location_id);
+        self.location_populations.insert(location_id, &(self.characters_in_location(location_id.clone()) + 1));
+    }
+
+    //remove the character from the location it is in, returning the location
+    pub(crate) fn internal_leave_location(&mut self, character_id: &TokenId) -> Option<AccountId> {
+        let location_id = self.character_locations.remove(character_id)?;
+        let population = self.characters_in_location(location_id.clone()) - 1;
+        if population == 0 {
+            self.location_populations.remove(&location_id);
+        } else {
+            self.location_populations.insert(&location_id, &population);
+        }
+        Some(location_id)
+    }
+}
@@ -1 +1,2 @@
-pub mod location;
\ No newline at end of file
+pub mod location;
+pub mod stats;
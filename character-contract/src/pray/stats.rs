@@ -0,0 +1,79 @@
+use crate::*;
+use std::collections::BTreeMap;
+
+//gameplay attributes of a revealed character, parsed from the `attributes` of its metadata
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CharacterStats {
+    //the "Class" attribute
+    pub class: Option<String>,
+    //every attribute with a numeric value, e.g. "Strength"
+    pub stats: BTreeMap<String, u32>,
+}
+
+impl CharacterStats {
+    /*
+        parse the `{"attributes": [{"trait_type": "Class", "value": "Villager"}, ...]}` written into `extra` by the metadata script.
+        attributes which are neither the class nor a number are left out, metadata without attributes has no stats.
+    */
+    pub fn from_metadata(metadata: &TokenMetadata) -> Option<Self> {
+        let extra: serde_json::Value = serde_json::from_str(metadata.extra.as_ref()?).ok()?;
+        let attributes = extra.get("attributes")?.as_array()?;
+
+        let mut character_stats = CharacterStats { class: None, stats: BTreeMap::new() };
+        for attribute in attributes {
+            let trait_type = match attribute.get("trait_type").and_then(|trait_type| trait_type.as_str()) {
+                Some(trait_type) => trait_type,
+                None => continue,
+            };
+            match attribute.get("value") {
+                Some(serde_json::Value::String(value)) if trait_type == "Class" => {
+                    character_stats.class = Some(value.clone());
+                }
+                Some(serde_json::Value::Number(value)) => {
+                    if let Some(value) = value.as_u64().and_then(|value| u32::try_from(value).ok()) {
+                        character_stats.stats.insert(trait_type.to_string(), value);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Some(character_stats)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /*
+        view the stats of a minted character, None until it is revealed.
+        locations read it with little gas, so it never decrypts: lazily revealed characters need `cache_character_stats` first.
+    */
+    pub fn character_stats(&self, token_id: TokenId) -> Option<CharacterStats> {
+        self.tokens_by_id.get(&token_id)?;
+        self.character_stats.get(&token_id)
+    }
+
+    /*
+        store the stats of lazily revealed characters, whose metadata is never written to the state.
+        anyone can call it once the reveal key is submitted. Returns the number of characters whose stats were stored
+    */
+    pub fn cache_character_stats(&mut self, token_ids: Vec<TokenId>) -> u32 {
+        assert!(self.reveal_key.is_some(), "Reveal key wasn't submitted");
+
+        let mut decrypted_batches = HashMap::new();
+        let mut cached = 0;
+        for token_id in token_ids {
+            if self.tokens_by_id.get(&token_id).is_none() || self.character_stats.get(&token_id).is_some() {
+                continue;
+            }
+            let character_stats = self
+                .internal_lazy_token_metadata(&token_id, &mut decrypted_batches)
+                .and_then(|metadata| CharacterStats::from_metadata(&metadata));
+            if let Some(character_stats) = character_stats {
+                self.character_stats.insert(&token_id, &character_stats);
+                cached += 1;
+            }
+        }
+        cached
+    }
+}
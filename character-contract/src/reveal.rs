@@ -2,34 +2,277 @@ use crate::*;
 use serde_json;
 
 use std::collections::HashMap;
+use std::fmt;
+
+//reasons a metadata batch can fail to reveal
+#[derive(Debug, PartialEq)]
+pub enum RevealError {
+    InvalidBase64,
+    TooShort,
+    UnsupportedVersion(u8),
+    //payload without a header while legacy payloads are not allowed
+    LegacyFormat,
+    WeakKeyDerivation,
+    ExcessiveKeyDerivation,
+    InvalidSalt,
+    KeyDerivation,
+    //salt or rounds differ from the ones the key was derived with
+    KdfMismatch,
+    //wrong key or tampered ciphertext
+    Decryption,
+    InvalidUtf8,
+    InvalidJson,
+    ProvenanceMismatch,
+    Encryption,
+}
+
+impl fmt::Display for RevealError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RevealError::InvalidBase64 => "ciphertext is not valid base64",
+            RevealError::TooShort => "ciphertext is too short",
+            RevealError::UnsupportedVersion(version) => return write!(f, "format version {} is not supported", version),
+            RevealError::LegacyFormat => "legacy single round payloads are not allowed",
+            RevealError::WeakKeyDerivation => "key derivation uses too few rounds",
+            RevealError::ExcessiveKeyDerivation => "key derivation uses too many rounds",
+            RevealError::InvalidSalt => "salt is invalid",
+            RevealError::KeyDerivation => "key derivation failed",
+            RevealError::KdfMismatch => "batch was encrypted with a different salt or number of rounds than the other batches",
+            RevealError::Decryption => "decryption failed, the key is wrong or the ciphertext was tampered with",
+            RevealError::InvalidUtf8 => "plaintext is not valid UTF-8",
+            RevealError::InvalidJson => "plaintext is not valid token metadata",
+            RevealError::ProvenanceMismatch => "plaintext doesn't match the provenance",
+            RevealError::Encryption => "encryption failed",
+        })
+    }
+}
+
+//how many metadata batches were revealed and how many are left
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RevealProgress {
+    pub revealed_batches: u64,
+    pub remaining_batches: u64,
+}
 
 #[near_bindgen]
 impl Contract {
     pub fn append_encrypted_metadata(&mut self, encrypted_metadata: String) {
-        self.assert_called_by_owner();
+        self.assert_has_role(Role::MetadataManager);
         assert!(self.collection_state < CollectionState::Published, "Can only append to the collection before it is published");
+        let params = match kdf_params(&encrypted_metadata) {
+            Ok(params) => params,
+            Err(error) => panic!("Invalid encrypted metadata: {}", error),
+        };
+        //the key is derived once for the whole collection when it's submitted
+        match &self.kdf_params {
+            Some(collection_params) => assert!(
+                collection_params == &params,
+                "Every metadata batch must be encrypted with the same salt and number of key derivation rounds"
+            ),
+            None => self.kdf_params = Some(params),
+        }
 
         self.encrypted_metadata.push(&encrypted_metadata);
     }
 
-    pub fn reveal(&mut self, password: String) -> bool {
-        self.assert_called_by_owner();
+    //commit to the sha256 hashes of the decrypted metadata batches, in the order they were appended
+    pub fn set_provenance(&mut self, batch_hashes: Vec<Base64VecU8>) {
+        self.assert_has_role(Role::MetadataManager);
+        assert!(self.collection_state < CollectionState::Published, "Provenance can't be changed after the collection is published");
+
+        self.provenance_batch_hashes = batch_hashes.iter().map(|hash| to_crypto_hash(&hash.0)).collect();
+    }
+
+    //view the provenance commitment: sha256 of all batch hashes concatenated
+    pub fn provenance_hash(&self) -> Option<Base64VecU8> {
+        if self.provenance_batch_hashes.is_empty() {
+            return None;
+        }
+        Some(Base64VecU8(env::sha256(&self.provenance_batch_hashes.concat())))
+    }
+
+    //view the committed hashes of the individual metadata batches
+    pub fn provenance_batch_hashes(&self) -> Vec<Base64VecU8> {
+        self.provenance_batch_hashes.iter().map(|hash| Base64VecU8(hash.to_vec())).collect()
+    }
+
+    /*
+        commit to the sha256 hash of the AES key derived from the reveal key with the salt and rounds of the batches,
+        before the collection is published. Checking a guess against it costs the key derivation, unlike a hash of the reveal key
+    */
+    pub fn commit_reveal_key(&mut self, key_hash: Base64VecU8) {
+        self.assert_has_role(Role::MetadataManager);
+        assert!(self.collection_state < CollectionState::Published, "Reveal key can't be changed after the collection is published");
+
+        self.reveal_key_hash = Some(to_crypto_hash(&key_hash.0));
+    }
+
+    //view the committed hash of the reveal key
+    pub fn reveal_key_hash(&self) -> Option<Base64VecU8> {
+        self.reveal_key_hash.map(|hash| Base64VecU8(hash.to_vec()))
+    }
+
+    /*
+        submit the key once it's time to reveal. The AES key is derived from it once and kept in the state,
+        so that revealing a batch, or a view decrypting one lazily, only pays for the decryption
+    */
+    pub fn submit_reveal_key(&mut self, key: String) {
+        self.assert_has_role(Role::Revealer);
+        assert!(self.collection_state >= CollectionState::Published, "Can't reveal metadata before the collection is published");
+        assert!(self.reveal_key.is_none(), "Reveal key was already submitted");
+
+        let key_hash = self.reveal_key_hash.expect("Reveal key wasn't committed");
+        let params = self.kdf_params.clone().expect("No metadata batch was uploaded");
+        let derived_key = match DerivedKey::derive(&key, params) {
+            Ok(derived_key) => derived_key,
+            Err(error) => panic!("Failed to derive the reveal key: {}", error),
+        };
+        assert_eq!(
+            to_crypto_hash(&env::sha256(&derived_key.key)),
+            key_hash,
+            "Reveal key doesn't match the commitment"
+        );
+        self.reveal_key = Some(derived_key);
+
+        //the metadata of the whole collection becomes visible when it is revealed lazily,
+        //which is logged once for the collection since its token ids don't fit in the logs of a call
+        if self.lazy_reveal_batch_size.is_some() {
+            let collection_metadata_update_log: EventLog = EventLog {
+                // Standard name ("pray").
+                standard: PRAY_STANDARD_NAME.to_string(),
+                // Version of the standard ("1.0.0").
+                version: PRAY_EVENTS_VERSION.to_string(),
+                // The data related with the event stored in a vector.
+                event: EventLogVariant::CollectionMetadataUpdate(vec![CollectionMetadataUpdateLog { collection_size: self.collection_size }]),
+            };
+            env::log_str(&collection_metadata_update_log.to_string());
+        }
+    }
+
+    //allow revealing legacy payloads which were encrypted with a single round key derivation
+    pub fn set_allow_legacy_kdf(&mut self, allow_legacy_kdf: bool) {
+        self.assert_has_role(Role::MetadataManager);
+
+        self.allow_legacy_kdf = allow_legacy_kdf;
+    }
+
+    pub fn allow_legacy_kdf(&self) -> bool {
+        self.allow_legacy_kdf
+    }
+
+    /*
+        reveal metadata lazily instead of looping `reveal`: once the reveal key is submitted, views decrypt
+        the batch holding a token on demand. Batches must hold `batch_size` consecutive token ids each.
+    */
+    pub fn set_lazy_reveal_batch_size(&mut self, batch_size: Option<u32>) {
+        self.assert_has_role(Role::MetadataManager);
+        assert!(self.collection_state < CollectionState::Published, "Reveal mode can't be changed after the collection is published");
+        assert_ne!(batch_size, Some(0), "Batch size must be positive");
+
+        self.lazy_reveal_batch_size = batch_size;
+    }
+
+    pub fn lazy_reveal_batch_size(&self) -> Option<u32> {
+        self.lazy_reveal_batch_size
+    }
+
+    //reveal the next metadata batch. Returns false once every batch was revealed
+    pub fn reveal(&mut self) -> bool {
+        self.assert_has_role(Role::Revealer);
         assert!(self.collection_state >= CollectionState::Published, "Can't reveal metadata before the collection is published");
+        let key = self.reveal_key.clone().expect("Reveal key wasn't submitted");
 
-        match self.encrypted_metadata.pop() {
-            Some(cyphertext) => {
-                let plaintext = aes_gcm_decrypt(&password, &cyphertext);
-                let mut data: HashMap<TokenId, TokenMetadata> = serde_json::from_str(&plaintext).unwrap();
+        //batches are revealed in the order they were appended and are kept in the state
+        let batch_index = self.revealed_batches;
+        let cyphertext = match self.encrypted_metadata.get(batch_index) {
+            Some(cyphertext) => cyphertext,
+            None => return false,
+        };
 
-                for (token_id, metadata) in data.iter_mut() {
-                    //insert the token ID and metadata
-                    metadata.copies = Some(1);
-                    self.token_metadata_by_id.insert(&token_id, &metadata);
-                }
+        let data = match self.internal_decrypt_batch(batch_index, &key, &cyphertext) {
+            Ok(data) => data,
+            Err(error) => panic!("Failed to reveal metadata batch {}: {}", batch_index, error),
+        };
 
-                true
-            },
-            None => false
+        let mut token_ids = Vec::with_capacity(data.len());
+        for (token_id, mut metadata) in data {
+            //insert the token ID and metadata
+            metadata.copies = Some(1);
+            if let Some(character_stats) = CharacterStats::from_metadata(&metadata) {
+                self.character_stats.insert(&token_id, &character_stats);
+            }
+            self.token_metadata_by_id.insert(&token_id, &metadata);
+            token_ids.push(token_id);
         }
+        self.revealed_batches += 1;
+
+        //log the revealed token ids in a stable order
+        token_ids.sort_by_key(|token_id| (token_id.len(), token_id.clone()));
+
+        // Construct the metadata update log as per the events standard.
+        let nft_metadata_update_log: EventLog = EventLog {
+            // Standard name ("nep171").
+            standard: NFT_STANDARD_NAME.to_string(),
+            // Version of the standard ("nft-1.0.0").
+            version: NFT_METADATA_SPEC.to_string(),
+            // The data related with the event stored in a vector.
+            event: EventLogVariant::NftMetadataUpdate(vec![NftMetadataUpdateLog { token_ids }]),
+        };
+
+        // Log the serialized json.
+        env::log_str(&nft_metadata_update_log.to_string());
+
+        true
+    }
+
+    //view how many metadata batches were revealed and how many are left
+    pub fn reveal_progress(&self) -> RevealProgress {
+        RevealProgress {
+            revealed_batches: self.revealed_batches,
+            remaining_batches: self.encrypted_metadata.len() - self.revealed_batches,
+        }
+    }
+}
+
+impl Contract {
+    //decrypt the batch and make sure it matches the provenance committed to before the collection was published
+    pub(crate) fn internal_decrypt_batch(
+        &self,
+        batch_index: u64,
+        key: &DerivedKey,
+        cyphertext: &str,
+    ) -> Result<HashMap<TokenId, TokenMetadata>, RevealError> {
+        let plaintext = aes_gcm_decrypt_with_key(key, cyphertext, self.allow_legacy_kdf)?;
+
+        let plaintext_hash = to_crypto_hash(&env::sha256(plaintext.as_bytes()));
+        if self.provenance_batch_hashes.get(batch_index as usize) != Some(&plaintext_hash) {
+            return Err(RevealError::ProvenanceMismatch);
+        }
+
+        serde_json::from_str(&plaintext).map_err(|_| RevealError::InvalidJson)
+    }
+
+    /*
+        metadata of a token which wasn't written by `reveal`, decrypted from its batch when metadata is revealed lazily.
+        decrypted batches are kept in `decrypted_batches` so that enumeration decrypts every batch once per call.
+        a batch which fails to decrypt leaves its tokens with the placeholder.
+    */
+    pub(crate) fn internal_lazy_token_metadata(
+        &self,
+        token_id: &TokenId,
+        decrypted_batches: &mut HashMap<u64, Option<HashMap<TokenId, TokenMetadata>>>,
+    ) -> Option<TokenMetadata> {
+        let batch_size = self.lazy_reveal_batch_size?;
+        let key = self.reveal_key.as_ref()?;
+        let batch_index = token_id.parse::<u64>().ok()? / u64::from(batch_size);
+
+        let batch = decrypted_batches.entry(batch_index).or_insert_with(|| {
+            let cyphertext = self.encrypted_metadata.get(batch_index)?;
+            self.internal_decrypt_batch(batch_index, key, &cyphertext).ok()
+        });
+        let mut metadata = batch.as_ref()?.get(token_id)?.clone();
+        metadata.copies = Some(1);
+        Some(metadata)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,186 @@
+use crate::*;
+use near_sdk::json_types::U64;
+use std::collections::HashSet;
+
+//who is allowed to mint during a sale phase
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum SaleAccess {
+    //only accounts on the phase allowlist can mint
+    Allowlist,
+    //anyone can mint
+    Public,
+    //nobody can mint
+    Closed,
+}
+
+//a single phase of the sale schedule. The phase lasts until the next phase starts
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SalePhase {
+    //unique name of the phase, also used to key the allowlist and the minted counters
+    pub name: String,
+    pub access: SaleAccess,
+    //when the phase starts, Unix epoch in nanoseconds
+    pub starts_at: U64,
+    //price of a single token during the phase
+    pub price: U128,
+    //how many tokens a single account can mint during the phase
+    pub max_per_account: Option<u32>,
+}
+
+#[near_bindgen]
+impl Contract {
+    //replace the sale schedule. Phases have to be sorted by their start time
+    pub fn set_sale_phases(&mut self, sale_phases: Vec<SalePhase>) {
+        self.assert_has_role(Role::SaleManager);
+
+        assert!(
+            sale_phases.windows(2).all(|pair| pair[0].starts_at.0 < pair[1].starts_at.0),
+            "Sale phases must be sorted by start time"
+        );
+        assert_eq!(
+            sale_phases.iter().map(|phase| &phase.name).collect::<HashSet<_>>().len(),
+            sale_phases.len(),
+            "Sale phase names must be unique"
+        );
+
+        self.sale_phases = sale_phases;
+    }
+
+    pub fn add_to_sale_allowlist(&mut self, phase_name: String, account_ids: Vec<AccountId>) {
+        self.assert_has_role(Role::SaleManager);
+
+        for account_id in account_ids {
+            self.sale_allowlist.insert(&(phase_name.clone(), account_id));
+        }
+    }
+
+    pub fn remove_from_sale_allowlist(&mut self, phase_name: String, account_ids: Vec<AccountId>) {
+        self.assert_has_role(Role::SaleManager);
+
+        for account_id in account_ids {
+            self.sale_allowlist.remove(&(phase_name.clone(), account_id));
+        }
+    }
+
+    //view the whole sale schedule
+    pub fn sale_phases(&self) -> Vec<SalePhase> {
+        self.sale_phases.clone()
+    }
+
+    //view the phase which is active right now
+    pub fn current_sale_phase(&self) -> Option<SalePhase> {
+        self.internal_sale_phase_at(env::block_timestamp())
+    }
+
+    //view when the phase after the current one starts, Unix epoch in nanoseconds
+    pub fn next_sale_phase_starts_at(&self) -> Option<U64> {
+        let now = env::block_timestamp();
+        self.sale_phases
+            .iter()
+            .find(|phase| phase.starts_at.0 > now)
+            .map(|phase| phase.starts_at)
+    }
+
+    //set the merkle root of the allowlist entries. Entries proven against it can mint during allowlist phases
+    pub fn set_allowlist_root(&mut self, allowlist_root: Option<Base64VecU8>) {
+        self.assert_has_role(Role::SaleManager);
+
+        self.allowlist_root = allowlist_root.map(|root| to_crypto_hash(&root.0));
+    }
+
+    pub fn allowlist_root(&self) -> Option<Base64VecU8> {
+        self.allowlist_root.map(|root| Base64VecU8(root.to_vec()))
+    }
+
+    //view how many tokens an account has minted against its merkle allowlist quota
+    pub fn allowlist_minted(&self, account_id: AccountId) -> u32 {
+        self.allowlist_minted.get(&account_id).unwrap_or(0)
+    }
+
+    pub fn is_on_sale_allowlist(&self, phase_name: String, account_id: AccountId) -> bool {
+        self.sale_allowlist.contains(&(phase_name, account_id))
+    }
+
+    //view how many tokens an account has minted during a phase
+    pub fn sale_minted_by(&self, phase_name: String, account_id: AccountId) -> u32 {
+        self.sale_minted_per_account.get(&(phase_name, account_id)).unwrap_or(0)
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_sale_phase_at(&self, timestamp: u64) -> Option<SalePhase> {
+        self.sale_phases
+            .iter()
+            .rev()
+            .find(|phase| phase.starts_at.0 <= timestamp)
+            .cloned()
+    }
+
+    /*
+        make sure that the account can mint `count` tokens during the active phase and record them.
+        returns the price of a single token. If no schedule was set, minting is public at the mint price.
+    */
+    pub(crate) fn internal_use_sale_quota(
+        &mut self,
+        account_id: &AccountId,
+        count: u32,
+        allowlist_proof: Option<AllowlistProof>,
+    ) -> Balance {
+        if self.sale_phases.is_empty() {
+            return self.mint_price;
+        }
+
+        let phase = self.current_sale_phase().expect("Sale has not started yet");
+        assert_ne!(phase.access, SaleAccess::Closed, "Sale is closed");
+
+        let key = (phase.name.clone(), account_id.clone());
+        if phase.access == SaleAccess::Allowlist {
+            match allowlist_proof {
+                Some(allowlist_proof) => self.internal_use_allowlist_quota(account_id, count, allowlist_proof),
+                None => assert!(
+                    self.sale_allowlist.contains(&key),
+                    "Account is not on the allowlist of the {} phase",
+                    phase.name
+                ),
+            }
+        }
+
+        let minted = self.sale_minted_per_account.get(&key).unwrap_or(0) + count;
+        if let Some(max_per_account) = phase.max_per_account {
+            assert!(
+                minted <= max_per_account,
+                "Can't mint more than {} tokens during the {} phase",
+                max_per_account,
+                phase.name
+            );
+        }
+        self.sale_minted_per_account.insert(&key, &minted);
+
+        phase.price.into()
+    }
+
+    //verify the merkle proof of the account's allowlist entry and record the tokens against its quota
+    pub(crate) fn internal_use_allowlist_quota(
+        &mut self,
+        account_id: &AccountId,
+        count: u32,
+        allowlist_proof: AllowlistProof,
+    ) {
+        let root = self.allowlist_root.expect("Allowlist root is not set");
+        let proof: Vec<CryptoHash> = allowlist_proof.proof.iter().map(|node| to_crypto_hash(&node.0)).collect();
+        assert!(
+            verify_merkle_proof(allowlist_leaf(account_id, allowlist_proof.quota), &proof, &root),
+            "Invalid allowlist proof"
+        );
+
+        let minted = self.allowlist_minted(account_id.clone()) + count;
+        assert!(
+            minted <= allowlist_proof.quota,
+            "Can't mint more than {} tokens on the allowlist",
+            allowlist_proof.quota
+        );
+        self.allowlist_minted.insert(account_id, &minted);
+    }
+}
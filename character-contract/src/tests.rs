@@ -1,11 +1,20 @@
 /* unit tests */
 #[cfg(test)]
-use crate::aes_gcm_decrypt;
+use crate::{aes_gcm_decrypt, aes_gcm_encrypt, derive_key, kdf_params, DerivedKey, MAX_KDF_ROUNDS};
+use crate::enumeration::LAZY_REVEAL_PAGE_LIMIT;
 use crate::Contract;
 use crate::CollectionState;
-use crate::TokenMetadata;
+use crate::{RevealError, RevealProgress};
+use crate::{Role, RoleGrant};
+use crate::PauseFlags;
+use crate::{Coordinates, JsonLocation, LocationMetadata, TransferLock};
+use crate::CharacterStats;
+use crate::{MAX_MIGRATED_CHARACTERS, STATE_VERSION};
+use crate::{SaleAccess, SalePhase};
+use crate::{allowlist_leaf, verify_merkle_proof, AllowlistProof, MerkleTree};
+use crate::{TokenId, TokenMetadata};
 use crate::approval::NonFungibleTokenCore;
-use near_sdk::json_types::{U128, U64};
+use near_sdk::json_types::{Base64VecU8, U128, U64};
 use near_sdk::test_utils::{accounts, VMContextBuilder};
 use near_sdk::testing_env;
 use near_sdk::{env, AccountId};
@@ -13,6 +22,9 @@ use near_sdk::{env, AccountId};
 use std::collections::HashMap;
 
 const MINT_STORAGE_COST: u128 = 100_000_000_000_000_000_000_000;
+const MINT_PRICE: u128 = 1_000_000_000_000_000_000_000_000;
+//metadata of token "0" encrypted with "password" in the legacy format
+const SAMPLE_ENCRYPTED_METADATA: &str = "kz41tI8/G3wBD8gLKesW4sFS7NhVuzT5+hCInwrEB4F3L5o1rxExO2vftINXaXQPOAVEeU6ESuNTw7DsWZP+iE+K+gLvYJ8W/eYg/M5LZkJ3YNwi1yD8OA0jwAebMChlMdBPrLfHIAIc9Jq7bLl6zedQnDBaQW+HZHbF33kkEr8avqZSW10GCrLNjKtZ5bwM6nDZRg39NoNOJvUni9ALWaOcRDsbXyDSzeBBKB/kcegj+Nh/AwHPn7/bBwje8n0IULv+VKBeQpDhznAgO6YxiZbGuNfcmSGIeg7idhwd0F3e3zZw7zX+k0vSewehaFiHGTiq8L8dMP4/37Xi4FgSw1BJhfP5VFuFc0GtbHxwiPMux/LugAFErmFoypDSdBOYiwsqWVNanKERWWjqub+99h/KfcWKOzXf8rmQIRT5+Q32NW8TeRMIJ5Xpcvow/k5eZaES9Zy+O7Xm6NNR5Eq0IsqFfI/Yb6oUmX5c6vOvjSR6z+atnrjzfxbA88IdV/kUrDPX";
 const MIN_REQUIRED_APPROVAL_YOCTO: u128 = 170000000000000000000;
 
 fn get_context(predecessor: AccountId) -> VMContextBuilder {
@@ -50,7 +62,7 @@ fn test_default() {
 fn test_new_account_contract() {
     let mut context = get_context(accounts(1));
     testing_env!(context.build());
-    let contract = Contract::new_default_meta(accounts(1).into(), 10, None);
+    let contract = Contract::new_default_meta(accounts(1).into(), 10, U128(MINT_PRICE), None, None);
     testing_env!(context.is_view(true).build());
     let contract_nft_tokens = contract.nft_tokens(Some(U128(0)), None);
     assert_eq!(contract_nft_tokens.len(), 0);
@@ -60,16 +72,15 @@ fn test_new_account_contract() {
 fn test_mint_nft() {
     let mut context = get_context(accounts(0));
     testing_env!(context.build());
-    let mut contract = Contract::new_default_meta(accounts(0).into(), 10, None);
+    let mut contract = Contract::new_default_meta(accounts(0).into(), 10, U128(MINT_PRICE), None, None);
     contract.set_collection_state(CollectionState::Published);
     testing_env!(context
         .storage_usage(env::storage_usage())
-        .attached_deposit(MINT_STORAGE_COST)
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
         .predecessor_account_id(accounts(0))
         .build());
     // let token_metadata: TokenMetadata = sample_token_metadata();
-    let token_id = "0".to_string();
-    contract.nft_mint(accounts(0));
+    let token_id = contract.nft_mint(accounts(0), None);
     let contract_nft_tokens = contract.nft_tokens(Some(U128(0)), None);
     assert_eq!(contract_nft_tokens.len(), 1);
 
@@ -94,16 +105,15 @@ fn test_mint_nft() {
 fn test_internal_transfer() {
     let mut context = get_context(accounts(0));
     testing_env!(context.build());
-    let mut contract = Contract::new_default_meta(accounts(0).into(), 10, None);
+    let mut contract = Contract::new_default_meta(accounts(0).into(), 10, U128(MINT_PRICE), None, None);
     contract.set_collection_state(CollectionState::Published);
 
     testing_env!(context
         .storage_usage(env::storage_usage())
-        .attached_deposit(MINT_STORAGE_COST)
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
         .predecessor_account_id(accounts(0))
         .build());
-    let token_id = "0".to_string();
-    contract.nft_mint(accounts(0));
+    let token_id = contract.nft_mint(accounts(0), None);
 
     testing_env!(context
         .storage_usage(env::storage_usage())
@@ -147,16 +157,15 @@ fn test_internal_transfer() {
 fn test_nft_approve() {
     let mut context = get_context(accounts(0));
     testing_env!(context.build());
-    let mut contract = Contract::new_default_meta(accounts(0).into(), 10, None);
+    let mut contract = Contract::new_default_meta(accounts(0).into(), 10, U128(MINT_PRICE), None, None);
     contract.set_collection_state(CollectionState::Published);
 
     testing_env!(context
         .storage_usage(env::storage_usage())
-        .attached_deposit(MINT_STORAGE_COST)
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
         .predecessor_account_id(accounts(0))
         .build());
-    let token_id = "0".to_string();
-    contract.nft_mint(accounts(0));
+    let token_id = contract.nft_mint(accounts(0), None);
 
     testing_env!(context
         .storage_usage(env::storage_usage())
@@ -164,6 +173,13 @@ fn test_nft_approve() {
         .predecessor_account_id(accounts(0))
         .build());
     contract.nft_approve(token_id.clone(), accounts(1), None);
+    assert_eq!(
+        near_sdk::test_utils::get_logs(),
+        vec![format!(
+            r#"EVENT_JSON:{{"standard":"nep171","version":"nft-1.0.0","event":"nft_approve","data":[{{"owner_id":"alice","account_id":"bob","approval_id":0,"token_ids":["{}"]}}]}}"#,
+            token_id
+        )]
+    );
 
     testing_env!(context
         .storage_usage(env::storage_usage())
@@ -178,16 +194,15 @@ fn test_nft_approve() {
 fn test_nft_revoke() {
     let mut context = get_context(accounts(0));
     testing_env!(context.build());
-    let mut contract = Contract::new_default_meta(accounts(0).into(), 10, None);
+    let mut contract = Contract::new_default_meta(accounts(0).into(), 10, U128(MINT_PRICE), None, None);
     contract.set_collection_state(CollectionState::Published);
 
     testing_env!(context
         .storage_usage(env::storage_usage())
-        .attached_deposit(MINT_STORAGE_COST)
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
         .predecessor_account_id(accounts(0))
         .build());
-    let token_id = "0".to_string();
-    contract.nft_mint(accounts(0));
+    let token_id = contract.nft_mint(accounts(0), None);
 
     // alice approves bob
     testing_env!(context
@@ -204,6 +219,13 @@ fn test_nft_revoke() {
         .predecessor_account_id(accounts(0))
         .build());
     contract.nft_revoke(token_id.clone(), accounts(1));
+    assert_eq!(
+        near_sdk::test_utils::get_logs(),
+        vec![format!(
+            r#"EVENT_JSON:{{"standard":"nep171","version":"nft-1.0.0","event":"nft_revoke","data":[{{"owner_id":"alice","account_id":"bob","token_ids":["{}"]}}]}}"#,
+            token_id
+        )]
+    );
     testing_env!(context
         .storage_usage(env::storage_usage())
         .account_balance(env::account_balance())
@@ -217,16 +239,15 @@ fn test_nft_revoke() {
 fn test_revoke_all() {
     let mut context = get_context(accounts(0));
     testing_env!(context.build());
-    let mut contract = Contract::new_default_meta(accounts(0).into(), 10, None);
+    let mut contract = Contract::new_default_meta(accounts(0).into(), 10, U128(MINT_PRICE), None, None);
     contract.set_collection_state(CollectionState::Published);
 
     testing_env!(context
         .storage_usage(env::storage_usage())
-        .attached_deposit(MINT_STORAGE_COST)
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
         .predecessor_account_id(accounts(0))
         .build());
-    let token_id = "0".to_string();
-    contract.nft_mint(accounts(0));
+    let token_id = contract.nft_mint(accounts(0), None);
 
     // alice approves bob
     testing_env!(context
@@ -243,6 +264,13 @@ fn test_revoke_all() {
         .predecessor_account_id(accounts(0))
         .build());
     contract.nft_revoke_all(token_id.clone());
+    assert_eq!(
+        near_sdk::test_utils::get_logs(),
+        vec![format!(
+            r#"EVENT_JSON:{{"standard":"nep171","version":"nft-1.0.0","event":"nft_revoke_all","data":[{{"owner_id":"alice","token_ids":["{}"]}}]}}"#,
+            token_id
+        )]
+    );
     testing_env!(context
         .storage_usage(env::storage_usage())
         .account_balance(env::account_balance())
@@ -256,16 +284,15 @@ fn test_revoke_all() {
 fn test_internal_remove_token_from_owner() {
     let mut context = get_context(accounts(0));
     testing_env!(context.build());
-    let mut contract = Contract::new_default_meta(accounts(0).into(), 10, None);
+    let mut contract = Contract::new_default_meta(accounts(0).into(), 10, U128(MINT_PRICE), None, None);
     contract.set_collection_state(CollectionState::Published);
 
     testing_env!(context
         .storage_usage(env::storage_usage())
-        .attached_deposit(MINT_STORAGE_COST)
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
         .predecessor_account_id(accounts(0))
         .build());
-    let token_id = "0".to_string();
-    contract.nft_mint(accounts(0));
+    let token_id = contract.nft_mint(accounts(0), None);
 
     let contract_nft_tokens_before = contract.nft_tokens_for_owner(accounts(0), None, None);
     assert_eq!(contract_nft_tokens_before.len(), 1);
@@ -280,16 +307,15 @@ fn test_nft_payout() {
     use crate::royalty::NonFungibleTokenCore;
     let mut context = get_context(accounts(0));
     testing_env!(context.build());
-    let mut contract = Contract::new_default_meta(accounts(0).into(), 10, None);
+    let mut contract = Contract::new_default_meta(accounts(0).into(), 10, U128(MINT_PRICE), None, None);
     contract.set_collection_state(CollectionState::Published);
 
     testing_env!(context
         .storage_usage(env::storage_usage())
-        .attached_deposit(MINT_STORAGE_COST)
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
         .predecessor_account_id(accounts(0))
         .build());
-    let token_id = "0".to_string();
-    contract.nft_mint(accounts(0));
+    let token_id = contract.nft_mint(accounts(0), None);
 
     // alice approves bob
     testing_env!(context
@@ -308,16 +334,16 @@ fn test_nft_payout() {
 fn test_nft_total_supply() {
     let mut context = get_context(accounts(0));
     testing_env!(context.build());
-    let mut contract = Contract::new_default_meta(accounts(0).into(), 10, None);
+    let mut contract = Contract::new_default_meta(accounts(0).into(), 10, U128(MINT_PRICE), None, None);
     contract.set_collection_state(CollectionState::Published);
 
     testing_env!(context
         .storage_usage(env::storage_usage())
-        .attached_deposit(MINT_STORAGE_COST)
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
         .predecessor_account_id(accounts(0))
         .build());
     // let token_id = "0".to_string();
-    contract.nft_mint(accounts(0));
+    contract.nft_mint(accounts(0), None);
 
     let total_supply = contract.nft_total_supply();
     assert_eq!(total_supply, U128(1));
@@ -325,26 +351,80 @@ fn test_nft_total_supply() {
 
 #[test]
 fn test_decryption() {
+    let password = "password";
+    //1000 rounds of key derivation
+    let encrypted = "UFJBWQHoAwAAKUpTYB75SwBbajRKL3WTdkxyncQHN3KJ1y7s2I9i7WuYaJ+pm8xJNIMHuHiwS4SbomUX5Z3Itswh2k/7quKXIkFAOVg=";
+    assert_eq!(aes_gcm_decrypt(password, encrypted, false), Ok("this is some random text".to_string()));
+    assert_eq!(aes_gcm_decrypt("wrong password", encrypted, false), Err(RevealError::Decryption));
+    assert_eq!(aes_gcm_decrypt(password, "not base64!", false), Err(RevealError::InvalidBase64));
+    assert_eq!(aes_gcm_decrypt(password, "UFJBWQE=", false), Err(RevealError::TooShort));
+    assert_eq!(aes_gcm_decrypt(password, "UFJBWQIKAAAA", false), Err(RevealError::UnsupportedVersion(2)));
+}
+
+#[test]
+fn test_decryption_weak_key_derivation() {
+    //10 rounds of key derivation
+    let encrypted = "UFJBWQEKAAAAFArOEUSkzIL4sStIIa7dAksJntlbtOZWt2ljX63dHcrJtgjL75sWJYymIJhhuBkS9XATS41qfTiD3sDJ3sXAtkVz3MU=";
+    assert_eq!(aes_gcm_decrypt("password", encrypted, true), Err(RevealError::WeakKeyDerivation));
+}
+
+#[test]
+fn test_decryption_excessive_key_derivation() {
+    assert_eq!(aes_gcm_decrypt("password", &excessive_kdf_payload(), false), Err(RevealError::ExcessiveKeyDerivation));
+    assert_eq!(
+        aes_gcm_encrypt("password", "text", MAX_KDF_ROUNDS + 1, &[1; 16], &[2; 12]),
+        Err(RevealError::ExcessiveKeyDerivation)
+    );
+}
+
+//versioned payload whose header asks for more key derivation rounds than the contract accepts
+fn excessive_kdf_payload() -> String {
+    let mut payload = b"PRAY\x01".to_vec();
+    payload.extend_from_slice(&(MAX_KDF_ROUNDS + 1).to_le_bytes());
+    payload.extend_from_slice(&[0; 44]);
+    base64::encode(payload)
+}
+
+#[test]
+fn test_decryption_legacy() {
     let password = "password";
     let encrypted = "FCb4EnlNN28kxpoeH+QTwnUbtRtdvVnP2ZIwwlR6gJVimQKfWykw1J27rA8KxC7krOT3AOfva1h5SjZB7h+OFQ18afg=";
-    assert_eq!(aes_gcm_decrypt(password, encrypted), "this is some random text");
+    assert_eq!(aes_gcm_decrypt(password, encrypted, true), Ok("this is some random text".to_string()));
+    assert_eq!(aes_gcm_decrypt(password, encrypted, false), Err(RevealError::LegacyFormat));
+}
+
+fn sample_metadata_hash() -> Base64VecU8 {
+    Base64VecU8(env::sha256(aes_gcm_decrypt("password", SAMPLE_ENCRYPTED_METADATA, true).unwrap().as_bytes()))
+}
+
+//commitment to the reveal key: the hash of the key derived from it with the parameters of the uploaded batches
+fn reveal_key_hash(contract: &Contract, password: &str) -> Base64VecU8 {
+    Base64VecU8(env::sha256(&DerivedKey::derive(password, contract.kdf_params.clone().unwrap()).unwrap().key))
+}
+
+fn sample_reveal_key_hash(contract: &Contract) -> Base64VecU8 {
+    reveal_key_hash(contract, "password")
 }
 
 #[test]
 fn test_release() {
     let mut context = get_context(accounts(0));
     testing_env!(context.build());
-    let mut contract = Contract::new_default_meta(accounts(0).into(), 10, None);
+    let mut contract = Contract::new_default_meta(accounts(0).into(), 1, U128(MINT_PRICE), None, None);
 
     testing_env!(context
         .storage_usage(env::storage_usage())
-        .attached_deposit(MINT_STORAGE_COST)
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
         .predecessor_account_id(accounts(0))
         .build());
-    contract.append_encrypted_metadata("kz41tI8/G3wBD8gLKesW4sFS7NhVuzT5+hCInwrEB4F3L5o1rxExO2vftINXaXQPOAVEeU6ESuNTw7DsWZP+iE+K+gLvYJ8W/eYg/M5LZkJ3YNwi1yD8OA0jwAebMChlMdBPrLfHIAIc9Jq7bLl6zedQnDBaQW+HZHbF33kkEr8avqZSW10GCrLNjKtZ5bwM6nDZRg39NoNOJvUni9ALWaOcRDsbXyDSzeBBKB/kcegj+Nh/AwHPn7/bBwje8n0IULv+VKBeQpDhznAgO6YxiZbGuNfcmSGIeg7idhwd0F3e3zZw7zX+k0vSewehaFiHGTiq8L8dMP4/37Xi4FgSw1BJhfP5VFuFc0GtbHxwiPMux/LugAFErmFoypDSdBOYiwsqWVNanKERWWjqub+99h/KfcWKOzXf8rmQIRT5+Q32NW8TeRMIJ5Xpcvow/k5eZaES9Zy+O7Xm6NNR5Eq0IsqFfI/Yb6oUmX5c6vOvjSR6z+atnrjzfxbA88IdV/kUrDPX".to_string());
+    contract.append_encrypted_metadata(SAMPLE_ENCRYPTED_METADATA.to_string());
+    contract.set_provenance(vec![sample_metadata_hash()]);
+    contract.commit_reveal_key(sample_reveal_key_hash(&contract));
+    contract.set_allow_legacy_kdf(true);
     contract.set_collection_state(CollectionState::Published);
-    contract.nft_mint(accounts(0));
-    contract.reveal("password".to_string());
+    contract.nft_mint(accounts(0), None);
+    contract.submit_reveal_key("password".to_string());
+    contract.reveal();
     
     let contract_nft_tokens = contract.nft_tokens(Some(U128(0)), None);
     assert_eq!(contract_nft_tokens.len(), 1);
@@ -363,4 +443,1582 @@ fn test_release() {
         contract_nft_tokens[0].metadata.media,
         Some("https://ipfs.io/ipfs/QmQskW3RWhbiYyebrgJTAA6BwkUcSuxbmAMKyVQbo27zRq/0.png".to_string())
     );
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_mint_price() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 10, U128(MINT_PRICE), None, None);
+    contract.set_collection_state(CollectionState::Published);
+    assert_eq!(contract.mint_price(), U128(MINT_PRICE));
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(1))
+        .build());
+    contract.nft_mint(accounts(1), None);
+    contract.nft_mint(accounts(1), None);
+    assert_eq!(contract.mint_proceeds(), U128(2 * MINT_PRICE));
+
+    testing_env!(context
+        .attached_deposit(0)
+        .predecessor_account_id(accounts(0))
+        .build());
+    contract.set_mint_price(U128(2 * MINT_PRICE));
+    assert_eq!(contract.mint_price(), U128(2 * MINT_PRICE));
+}
+
+#[test]
+#[should_panic(expected = "to cover price and storage")]
+fn test_mint_without_price() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 10, U128(MINT_PRICE), None, None);
+    contract.set_collection_state(CollectionState::Published);
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(1))
+        .build());
+    contract.nft_mint(accounts(1), None);
+}
+
+#[test]
+fn test_withdraw_proceeds() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 10, U128(MINT_PRICE), None, None);
+    contract.set_collection_state(CollectionState::Published);
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(1))
+        .build());
+    contract.nft_mint(accounts(1), None);
+    contract.nft_mint(accounts(1), None);
+
+    testing_env!(context
+        .attached_deposit(0)
+        .predecessor_account_id(accounts(0))
+        .build());
+    contract.withdraw_proceeds(Some(U128(MINT_PRICE)));
+    assert_eq!(contract.mint_proceeds(), U128(MINT_PRICE));
+    contract.withdraw_proceeds(None);
+    assert_eq!(contract.mint_proceeds(), U128(0));
+}
+
+#[test]
+#[should_panic(expected = "Requires the owner or the Treasurer role")]
+fn test_withdraw_proceeds_not_owner() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 10, U128(MINT_PRICE), None, None);
+
+    testing_env!(context.predecessor_account_id(accounts(1)).build());
+    contract.withdraw_proceeds(None);
+}
+
+fn sample_sale_phases() -> Vec<SalePhase> {
+    vec![
+        SalePhase {
+            name: "presale".to_string(),
+            access: SaleAccess::Allowlist,
+            starts_at: U64(100),
+            price: U128(MINT_PRICE / 2),
+            max_per_account: Some(1),
+        },
+        SalePhase {
+            name: "public".to_string(),
+            access: SaleAccess::Public,
+            starts_at: U64(200),
+            price: U128(MINT_PRICE),
+            max_per_account: Some(2),
+        },
+        SalePhase {
+            name: "closed".to_string(),
+            access: SaleAccess::Closed,
+            starts_at: U64(300),
+            price: U128(0),
+            max_per_account: None,
+        },
+    ]
+}
+
+fn get_sale_contract(context: &mut VMContextBuilder) -> Contract {
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 10, U128(MINT_PRICE), None, None);
+    contract.set_collection_state(CollectionState::Published);
+    contract.set_sale_phases(sample_sale_phases());
+    contract.add_to_sale_allowlist("presale".to_string(), vec![accounts(1)]);
+    contract
+}
+
+#[test]
+fn test_sale_phases() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_sale_contract(&mut context);
+
+    testing_env!(context.block_timestamp(50).is_view(true).build());
+    assert_eq!(contract.current_sale_phase(), None);
+    assert_eq!(contract.next_sale_phase_starts_at(), Some(U64(100)));
+
+    testing_env!(context.block_timestamp(150).build());
+    assert_eq!(contract.current_sale_phase().unwrap().name, "presale");
+    assert_eq!(contract.next_sale_phase_starts_at(), Some(U64(200)));
+
+    testing_env!(context.block_timestamp(350).build());
+    assert_eq!(contract.current_sale_phase().unwrap().access, SaleAccess::Closed);
+    assert_eq!(contract.next_sale_phase_starts_at(), None);
+
+    testing_env!(context
+        .block_timestamp(150)
+        .is_view(false)
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(1))
+        .build());
+    contract.nft_mint(accounts(1), None);
+    assert_eq!(contract.mint_proceeds(), U128(MINT_PRICE / 2));
+    assert_eq!(contract.sale_minted_by("presale".to_string(), accounts(1)), 1);
+
+    testing_env!(context.block_timestamp(250).build());
+    contract.nft_mint(accounts(1), None);
+    contract.nft_mint(accounts(1), None);
+    assert_eq!(contract.mint_proceeds(), U128(MINT_PRICE / 2 + 2 * MINT_PRICE));
+}
+
+#[test]
+#[should_panic(expected = "Account is not on the allowlist of the presale phase")]
+fn test_sale_presale_not_allowlisted() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_sale_contract(&mut context);
+
+    testing_env!(context
+        .block_timestamp(150)
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(2))
+        .build());
+    contract.nft_mint(accounts(2), None);
+}
+
+#[test]
+#[should_panic(expected = "Can't mint more than 1 tokens during the presale phase")]
+fn test_sale_presale_cap() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_sale_contract(&mut context);
+
+    testing_env!(context
+        .block_timestamp(150)
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(1))
+        .build());
+    contract.nft_mint(accounts(1), None);
+    contract.nft_mint(accounts(1), None);
+}
+
+#[test]
+#[should_panic(expected = "Sale has not started yet")]
+fn test_sale_not_started() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_sale_contract(&mut context);
+
+    testing_env!(context
+        .block_timestamp(50)
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(1))
+        .build());
+    contract.nft_mint(accounts(1), None);
+}
+
+#[test]
+#[should_panic(expected = "Sale is closed")]
+fn test_sale_closed() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_sale_contract(&mut context);
+
+    testing_env!(context
+        .block_timestamp(350)
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(1))
+        .build());
+    contract.nft_mint(accounts(1), None);
+}
+
+#[test]
+#[should_panic(expected = "Sale phases must be sorted by start time")]
+fn test_sale_phases_unsorted() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_sale_contract(&mut context);
+
+    let mut sale_phases = sample_sale_phases();
+    sale_phases.reverse();
+    contract.set_sale_phases(sale_phases);
+}
+
+fn sample_allowlist_tree() -> MerkleTree {
+    MerkleTree::new(vec![
+        allowlist_leaf(&accounts(1), 2),
+        allowlist_leaf(&accounts(2), 1),
+        allowlist_leaf(&accounts(3), 1),
+    ])
+}
+
+fn sample_allowlist_proof(tree: &MerkleTree, index: usize, quota: u32) -> Option<AllowlistProof> {
+    Some(AllowlistProof {
+        quota,
+        proof: tree.proof(index).iter().map(|node| Base64VecU8(node.to_vec())).collect(),
+    })
+}
+
+#[test]
+fn test_merkle_tree() {
+    let tree = sample_allowlist_tree();
+    let root = tree.root();
+    assert!(verify_merkle_proof(allowlist_leaf(&accounts(1), 2), &tree.proof(0), &root));
+    assert!(verify_merkle_proof(allowlist_leaf(&accounts(2), 1), &tree.proof(1), &root));
+    assert!(verify_merkle_proof(allowlist_leaf(&accounts(3), 1), &tree.proof(2), &root));
+    assert!(!verify_merkle_proof(allowlist_leaf(&accounts(3), 2), &tree.proof(2), &root));
+    assert!(!verify_merkle_proof(allowlist_leaf(&accounts(4), 1), &tree.proof(2), &root));
+}
+
+#[test]
+fn test_sale_merkle_allowlist() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_sale_contract(&mut context);
+    let tree = sample_allowlist_tree();
+    contract.set_allowlist_root(Some(Base64VecU8(tree.root().to_vec())));
+
+    testing_env!(context
+        .block_timestamp(150)
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(2))
+        .build());
+    contract.nft_mint(accounts(2), sample_allowlist_proof(&tree, 1, 1));
+    assert_eq!(contract.allowlist_minted(accounts(2)), 1);
+}
+
+#[test]
+#[should_panic(expected = "Invalid allowlist proof")]
+fn test_sale_merkle_allowlist_invalid_proof() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_sale_contract(&mut context);
+    let tree = sample_allowlist_tree();
+    contract.set_allowlist_root(Some(Base64VecU8(tree.root().to_vec())));
+
+    testing_env!(context
+        .block_timestamp(150)
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(2))
+        .build());
+    contract.nft_mint(accounts(2), sample_allowlist_proof(&tree, 1, 2));
+}
+
+#[test]
+#[should_panic(expected = "Can't mint more than 1 tokens on the allowlist")]
+fn test_sale_merkle_allowlist_quota() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_sale_contract(&mut context);
+    let tree = sample_allowlist_tree();
+    contract.set_allowlist_root(Some(Base64VecU8(tree.root().to_vec())));
+    contract.set_sale_phases(vec![SalePhase {
+        max_per_account: None,
+        ..sample_sale_phases()[0].clone()
+    }]);
+
+    testing_env!(context
+        .block_timestamp(150)
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(3))
+        .build());
+    contract.nft_mint(accounts(3), sample_allowlist_proof(&tree, 2, 1));
+    contract.nft_mint(accounts(3), sample_allowlist_proof(&tree, 2, 1));
+}
+
+#[test]
+fn test_batch_mint() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 10, U128(MINT_PRICE), None, None);
+    contract.set_collection_state(CollectionState::Published);
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(3 * (MINT_PRICE + MINT_STORAGE_COST))
+        .predecessor_account_id(accounts(1))
+        .build());
+    let token_ids = contract.nft_batch_mint(accounts(1), 3, None);
+
+    assert_eq!(contract.nft_total_supply(), U128(3));
+    assert_eq!(contract.nft_supply_for_owner(accounts(1)), U128(3));
+    assert_eq!(contract.mint_proceeds(), U128(3 * MINT_PRICE));
+    let logs = near_sdk::test_utils::get_logs();
+    assert_eq!(logs.len(), 1);
+    assert!(logs[0].contains(&format!(r#""token_ids":["{}","{}","{}"]"#, token_ids[0], token_ids[1], token_ids[2])));
+}
+
+#[test]
+#[should_panic(expected = "Only 2 tokens are left in the collection")]
+fn test_batch_mint_collection_size() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 2, U128(MINT_PRICE), None, None);
+    contract.set_collection_state(CollectionState::Published);
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(3 * (MINT_PRICE + MINT_STORAGE_COST))
+        .predecessor_account_id(accounts(1))
+        .build());
+    contract.nft_batch_mint(accounts(1), 3, None);
+}
+
+#[test]
+#[should_panic(expected = "Can't mint more than 2 tokens during the public phase")]
+fn test_batch_mint_phase_cap() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_sale_contract(&mut context);
+
+    testing_env!(context
+        .block_timestamp(250)
+        .storage_usage(env::storage_usage())
+        .attached_deposit(3 * (MINT_PRICE + MINT_STORAGE_COST))
+        .predecessor_account_id(accounts(1))
+        .build());
+    contract.nft_batch_mint(accounts(1), 3, None);
+}
+
+#[test]
+#[should_panic(expected = "to cover price and storage")]
+fn test_batch_mint_deposit() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 10, U128(MINT_PRICE), None, None);
+    contract.set_collection_state(CollectionState::Published);
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(1))
+        .build());
+    contract.nft_batch_mint(accounts(1), 3, None);
+}
+
+#[test]
+fn test_mint_random_token_ids() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 50, U128(MINT_PRICE), None, None);
+    contract.set_collection_state(CollectionState::Published);
+
+    let mut token_ids = Vec::new();
+    for seed in 0..5u8 {
+        testing_env!(context
+            .random_seed([seed; 32])
+            .storage_usage(env::storage_usage())
+            .attached_deposit(10 * (MINT_PRICE + MINT_STORAGE_COST))
+            .predecessor_account_id(accounts(1))
+            .build());
+        token_ids.extend(contract.nft_batch_mint(accounts(1), 10, None));
+    }
+
+    //every token id of the collection was drawn exactly once and not in the sequential order
+    let mut sorted_token_ids: Vec<u32> = token_ids.iter().map(|token_id| token_id.parse().unwrap()).collect();
+    assert_ne!(token_ids, (0..50).map(|token_id: u32| token_id.to_string()).collect::<Vec<_>>());
+    sorted_token_ids.sort_unstable();
+    assert_eq!(sorted_token_ids, (0..50).collect::<Vec<_>>());
+}
+
+fn get_release_contract(context: &mut VMContextBuilder) -> Contract {
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 1, U128(MINT_PRICE), None, None);
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(0))
+        .build());
+    contract.append_encrypted_metadata(SAMPLE_ENCRYPTED_METADATA.to_string());
+    contract.set_allow_legacy_kdf(true);
+    contract
+}
+
+#[test]
+fn test_provenance() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_release_contract(&mut context);
+    assert_eq!(contract.provenance_hash(), None);
+
+    contract.set_provenance(vec![sample_metadata_hash()]);
+    assert_eq!(contract.provenance_batch_hashes(), vec![sample_metadata_hash()]);
+    assert_eq!(
+        contract.provenance_hash(),
+        Some(Base64VecU8(env::sha256(&sample_metadata_hash().0)))
+    );
+}
+
+#[test]
+#[should_panic(expected = "Invalid encrypted metadata: key derivation uses too many rounds")]
+fn test_append_excessive_key_derivation() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_release_contract(&mut context);
+    contract.append_encrypted_metadata(excessive_kdf_payload());
+}
+
+#[test]
+#[should_panic(expected = "Provenance must be committed for every metadata batch before the collection is published")]
+fn test_publish_without_provenance() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_release_contract(&mut context);
+    contract.set_collection_state(CollectionState::Published);
+}
+
+#[test]
+#[should_panic(expected = "Provenance can't be changed after the collection is published")]
+fn test_provenance_after_publish() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_release_contract(&mut context);
+    contract.set_provenance(vec![sample_metadata_hash()]);
+    contract.commit_reveal_key(sample_reveal_key_hash(&contract));
+    contract.set_collection_state(CollectionState::Published);
+    contract.set_provenance(vec![Base64VecU8(vec![0; 32])]);
+}
+
+#[test]
+#[should_panic(expected = "Failed to reveal metadata batch 0: plaintext doesn't match the provenance")]
+fn test_reveal_provenance_mismatch() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_release_contract(&mut context);
+    contract.set_provenance(vec![Base64VecU8(vec![0; 32])]);
+    contract.commit_reveal_key(sample_reveal_key_hash(&contract));
+    contract.set_collection_state(CollectionState::Published);
+    contract.nft_mint(accounts(0), None);
+    contract.submit_reveal_key("password".to_string());
+    contract.reveal();
+}
+
+fn get_published_release_contract(context: &mut VMContextBuilder) -> Contract {
+    let mut contract = get_release_contract(context);
+    contract.set_provenance(vec![sample_metadata_hash()]);
+    contract.commit_reveal_key(sample_reveal_key_hash(&contract));
+    contract.set_collection_state(CollectionState::Published);
+    contract.nft_mint(accounts(0), None);
+    contract
+}
+
+#[test]
+#[should_panic(expected = "Reveal key must be committed before the collection is published")]
+fn test_publish_without_reveal_key() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_release_contract(&mut context);
+    contract.set_provenance(vec![sample_metadata_hash()]);
+    contract.set_collection_state(CollectionState::Published);
+}
+
+#[test]
+#[should_panic(expected = "Reveal key doesn't match the commitment")]
+fn test_submit_wrong_reveal_key() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_published_release_contract(&mut context);
+    contract.submit_reveal_key("wrong password".to_string());
+}
+
+#[test]
+#[should_panic(expected = "Reveal key doesn't match the commitment")]
+fn test_submit_reveal_key_password_hash() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_release_contract(&mut context);
+    contract.set_provenance(vec![sample_metadata_hash()]);
+    //a plain hash of the password would be checked against guesses without the cost of the key derivation
+    contract.commit_reveal_key(Base64VecU8(env::sha256(b"password")));
+    contract.set_collection_state(CollectionState::Published);
+    contract.submit_reveal_key("password".to_string());
+}
+
+#[test]
+#[should_panic(expected = "Reveal key was already submitted")]
+fn test_submit_reveal_key_twice() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_published_release_contract(&mut context);
+    contract.submit_reveal_key("password".to_string());
+    contract.submit_reveal_key("password".to_string());
+}
+
+#[test]
+#[should_panic(expected = "Reveal key wasn't submitted")]
+fn test_reveal_without_key() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_published_release_contract(&mut context);
+    contract.reveal();
+}
+
+#[test]
+#[should_panic(expected = "Failed to reveal metadata batch 0: decryption failed")]
+fn test_reveal_undecryptable_batch() {
+    let context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 1, U128(MINT_PRICE), None, None);
+    //the batch is encrypted with a different key than the committed one
+    contract.append_encrypted_metadata(SAMPLE_ENCRYPTED_METADATA.to_string());
+    contract.set_provenance(vec![sample_metadata_hash()]);
+    contract.commit_reveal_key(reveal_key_hash(&contract, "another password"));
+    contract.set_allow_legacy_kdf(true);
+    contract.set_collection_state(CollectionState::Published);
+    contract.submit_reveal_key("another password".to_string());
+    contract.reveal();
+}
+
+#[test]
+fn test_reveal_progress() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_published_release_contract(&mut context);
+    contract.submit_reveal_key("password".to_string());
+    assert_eq!(contract.reveal_progress(), RevealProgress { revealed_batches: 0, remaining_batches: 1 });
+
+    assert!(contract.reveal());
+    assert_eq!(contract.reveal_progress(), RevealProgress { revealed_batches: 1, remaining_batches: 0 });
+    assert_eq!(
+        near_sdk::test_utils::get_logs().last().unwrap(),
+        r#"EVENT_JSON:{"standard":"nep171","version":"nft-1.0.0","event":"nft_metadata_update","data":[{"token_ids":["0"]}]}"#
+    );
+    //the ciphertext is kept in the state after the batch was revealed
+    assert_eq!(contract.encrypted_metadata.len(), 1);
+
+    assert!(!contract.reveal());
+    assert_eq!(contract.reveal_progress(), RevealProgress { revealed_batches: 1, remaining_batches: 0 });
+}
+
+#[test]
+#[should_panic(expected = "Failed to reveal metadata batch 0: legacy single round payloads are not allowed")]
+fn test_reveal_legacy_not_allowed() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_published_release_contract(&mut context);
+    contract.set_allow_legacy_kdf(false);
+    contract.submit_reveal_key("password".to_string());
+    contract.reveal();
+}
+
+#[test]
+fn test_lazy_reveal() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_release_contract(&mut context);
+    contract.set_lazy_reveal_batch_size(Some(1));
+    contract.set_provenance(vec![sample_metadata_hash()]);
+    contract.commit_reveal_key(sample_reveal_key_hash(&contract));
+    contract.set_collection_state(CollectionState::Published);
+    contract.nft_mint(accounts(0), None);
+    assert_eq!(contract.nft_tokens(None, None)[0].metadata.title, Some("Mistery".to_string()));
+
+    //views decrypt the metadata once the key is submitted, without writing it into the state
+    contract.submit_reveal_key("password".to_string());
+    let token = &contract.nft_tokens(None, None)[0];
+    assert_eq!(token.metadata.title, Some("Villager 0".to_string()));
+    assert_eq!(token.metadata.copies, Some(1));
+    assert_eq!(contract.nft_tokens_for_owner(accounts(0), None, None)[0].metadata.title, Some("Villager 0".to_string()));
+    assert!(contract.token_metadata_by_id.get(&"0".to_string()).is_none());
+    assert_eq!(contract.reveal_progress(), RevealProgress { revealed_batches: 0, remaining_batches: 1 });
+}
+
+#[test]
+#[should_panic(expected = "Lazy reveal expects 2 metadata batches of 1 tokens")]
+fn test_lazy_reveal_batch_count() {
+    let context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 2, U128(MINT_PRICE), None, None);
+    contract.append_encrypted_metadata(SAMPLE_ENCRYPTED_METADATA.to_string());
+    contract.set_provenance(vec![sample_metadata_hash()]);
+    contract.commit_reveal_key(sample_reveal_key_hash(&contract));
+    contract.set_lazy_reveal_batch_size(Some(1));
+    contract.set_collection_state(CollectionState::Published);
+}
+
+//published collection revealed lazily, with a batch per token, where every token is minted by alice
+fn get_lazy_release_contract(context: &mut VMContextBuilder, collection_size: u32) -> Contract {
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), collection_size, U128(MINT_PRICE), None, None);
+    contract.set_lazy_reveal_batch_size(Some(1));
+
+    let mut batch_hashes = Vec::new();
+    for token_id in 0..collection_size {
+        let plaintext = near_sdk::serde_json::json!({
+            token_id.to_string(): {
+                "title": format!("Villager {}", token_id),
+                "extra": r#"{"attributes":[{"trait_type":"Class","value":"Miner"},{"trait_type":"Strength","value":7}]}"#,
+            }
+        }).to_string();
+        contract.append_encrypted_metadata(aes_gcm_encrypt("password", &plaintext, 1_000, &[1; 16], &[2; 12]).unwrap());
+        batch_hashes.push(Base64VecU8(env::sha256(plaintext.as_bytes())));
+    }
+    contract.set_provenance(batch_hashes);
+    contract.commit_reveal_key(sample_reveal_key_hash(&contract));
+    contract.set_collection_state(CollectionState::Published);
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(collection_size as u128 * (MINT_PRICE + MINT_STORAGE_COST))
+        .predecessor_account_id(accounts(0))
+        .build());
+    contract.nft_batch_mint(accounts(0), collection_size, None);
+    contract
+}
+
+#[test]
+fn test_lazy_reveal_pages() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_lazy_release_contract(&mut context, 25);
+    assert_eq!(contract.nft_tokens(None, None).len(), 25);
+
+    //the derived key is kept instead of the reveal key
+    contract.submit_reveal_key("password".to_string());
+    assert_eq!(contract.reveal_key.as_ref().unwrap().key, derive_key("password", &[1; 16], 1_000).unwrap());
+
+    //every token of a page decrypts a different batch, so the pages are capped
+    let mut token_ids = Vec::new();
+    for from_index in [0, 10, 20] {
+        let tokens = contract.nft_tokens(Some(U128(from_index)), Some(50));
+        assert_eq!(tokens.len(), (25 - from_index as usize).min(LAZY_REVEAL_PAGE_LIMIT as usize));
+        for token in tokens {
+            assert_eq!(token.metadata.title, Some(format!("Villager {}", token.token_id)));
+            token_ids.push(token.token_id);
+        }
+    }
+    token_ids.sort_by_key(|token_id| token_id.parse::<u32>().unwrap());
+    assert_eq!(token_ids, (0..25).map(|token_id| token_id.to_string()).collect::<Vec<_>>());
+    assert_eq!(contract.nft_tokens_for_owner(accounts(0), None, Some(50)).len(), LAZY_REVEAL_PAGE_LIMIT as usize);
+}
+
+#[test]
+fn test_lazy_reveal_metadata_update() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_lazy_release_contract(&mut context, 3);
+
+    //indexers refresh the metadata of the whole collection once it can be decrypted
+    testing_env!(context.attached_deposit(0).build());
+    contract.submit_reveal_key("password".to_string());
+    assert_eq!(
+        near_sdk::test_utils::get_logs(),
+        vec![r#"EVENT_JSON:{"standard":"pray","version":"1.0.0","event":"collection_metadata_update","data":[{"collection_size":3}]}"#]
+    );
+}
+
+#[test]
+fn test_lazy_reveal_metadata_update_large_collection() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 10_000, U128(MINT_PRICE), None, None);
+    contract.set_lazy_reveal_batch_size(Some(1_000));
+    let mut batch_hashes = Vec::new();
+    for _ in 0..10 {
+        contract.append_encrypted_metadata(aes_gcm_encrypt("password", "{}", 1_000, &[1; 16], &[2; 12]).unwrap());
+        batch_hashes.push(Base64VecU8(env::sha256(b"{}")));
+    }
+    contract.set_provenance(batch_hashes);
+    contract.commit_reveal_key(sample_reveal_key_hash(&contract));
+    contract.set_collection_state(CollectionState::Published);
+
+    //a single event stays within the size limit of the logs, which the ids of 10k tokens would exceed
+    testing_env!(context.attached_deposit(0).build());
+    contract.submit_reveal_key("password".to_string());
+    assert_eq!(
+        near_sdk::test_utils::get_logs(),
+        vec![r#"EVENT_JSON:{"standard":"pray","version":"1.0.0","event":"collection_metadata_update","data":[{"collection_size":10000}]}"#]
+    );
+}
+
+#[test]
+#[should_panic(expected = "Every metadata batch must be encrypted with the same salt and number of key derivation rounds")]
+fn test_append_different_salt() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_release_contract(&mut context);
+    contract.append_encrypted_metadata(aes_gcm_encrypt("password", "{}", 1_000, &[1; 16], &[2; 12]).unwrap());
+}
+
+#[test]
+fn test_nft_burn() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 3, U128(MINT_PRICE), None, None);
+    contract.set_collection_state(CollectionState::Published);
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(1))
+        .build());
+    let burned_token_id = contract.nft_mint(accounts(1), None);
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(1)
+        .predecessor_account_id(accounts(1))
+        .build());
+    contract.nft_burn(burned_token_id.clone(), None, None);
+    assert_eq!(
+        near_sdk::test_utils::get_logs(),
+        vec![format!(
+            r#"EVENT_JSON:{{"standard":"nep171","version":"nft-1.0.0","event":"nft_burn","data":[{{"owner_id":"bob","token_ids":["{}"]}}]}}"#,
+            burned_token_id
+        )]
+    );
+    assert_eq!(contract.nft_total_supply(), U128(0));
+    assert_eq!(contract.nft_supply_for_owner(accounts(1)), U128(0));
+    assert!(contract.nft_tokens_for_owner(accounts(1), None, None).is_empty());
+
+    //the rest of the collection can be minted, but never the burned id
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(2 * (MINT_PRICE + MINT_STORAGE_COST))
+        .predecessor_account_id(accounts(1))
+        .build());
+    let token_ids = contract.nft_batch_mint(accounts(1), 2, None);
+    assert!(!token_ids.contains(&burned_token_id));
+    assert_eq!(contract.nft_total_supply(), U128(2));
+}
+
+#[test]
+fn test_nft_burn_revealed_refund() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 1, U128(MINT_PRICE), None, None);
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(0))
+        .build());
+    let plaintext = near_sdk::serde_json::json!({
+        "0": { "title": "Villager 0", "extra": r#"{"attributes":[{"trait_type":"Class","value":"Miner"},{"trait_type":"Strength","value":7}]}"# }
+    }).to_string();
+    contract.append_encrypted_metadata(aes_gcm_encrypt("password", &plaintext, 1_000, &[1; 16], &[2; 12]).unwrap());
+    contract.set_provenance(vec![Base64VecU8(env::sha256(plaintext.as_bytes()))]);
+    contract.commit_reveal_key(sample_reveal_key_hash(&contract));
+    contract.set_collection_state(CollectionState::Published);
+    let token_id = contract.nft_mint(accounts(1), None);
+    contract.submit_reveal_key("password".to_string());
+    contract.reveal();
+
+    //the storage of the revealed metadata and stats, which the contract paid for
+    let revealed_metadata = contract.token_metadata_by_id.remove(&token_id).unwrap();
+    let revealed_stats = contract.character_stats.remove(&token_id).unwrap();
+    let storage_without_reveal = env::storage_usage();
+    contract.token_metadata_by_id.insert(&token_id, &revealed_metadata);
+    contract.character_stats.insert(&token_id, &revealed_stats);
+    let revealed_storage = env::storage_usage() - storage_without_reveal;
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(1)
+        .predecessor_account_id(accounts(1))
+        .build());
+    let initial_storage_usage = env::storage_usage();
+    contract.nft_burn(token_id.clone(), None, None);
+    let storage_released = initial_storage_usage - env::storage_usage();
+    assert!(contract.token_metadata_by_id.get(&token_id).is_none());
+    assert!(contract.character_stats.get(&token_id).is_none());
+
+    //only the token, its approvals and the owner's set are refunded to the owner
+    let receipts = near_sdk::test_utils::get_created_receipts();
+    assert_eq!(receipts.len(), 1);
+    assert_eq!(receipts[0].receiver_id, accounts(1));
+    assert!(matches!(
+        receipts[0].actions[0],
+        near_sdk::mock::VmAction::Transfer { deposit }
+            if deposit == u128::from(storage_released - revealed_storage) * env::storage_byte_cost()
+    ));
+    assert!(revealed_storage > 0);
+}
+
+#[test]
+fn test_nft_burn_approved() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 10, U128(MINT_PRICE), None, None);
+    contract.set_collection_state(CollectionState::Published);
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(0))
+        .build());
+    let token_id = contract.nft_mint(accounts(0), None);
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MIN_REQUIRED_APPROVAL_YOCTO)
+        .predecessor_account_id(accounts(0))
+        .build());
+    contract.nft_approve(token_id.clone(), accounts(1), None);
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(1)
+        .predecessor_account_id(accounts(1))
+        .build());
+    contract.nft_burn(token_id.clone(), Some(0), Some("sacrifice".to_string()));
+    assert!(near_sdk::test_utils::get_logs()[0].contains(r#""owner_id":"alice","authorized_id":"bob""#));
+    assert!(contract.tokens_by_id.get(&token_id).is_none());
+    assert_eq!(contract.nft_total_supply(), U128(0));
+}
+
+#[test]
+#[should_panic(expected = "Unauthorized")]
+fn test_nft_burn_unauthorized() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 10, U128(MINT_PRICE), None, None);
+    contract.set_collection_state(CollectionState::Published);
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(0))
+        .build());
+    let token_id = contract.nft_mint(accounts(0), None);
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(1)
+        .predecessor_account_id(accounts(1))
+        .build());
+    contract.nft_burn(token_id, None, None);
+}
+
+#[test]
+fn test_ownership_transfer() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 10, U128(MINT_PRICE), None, None);
+    contract.propose_owner(Some(accounts(1)));
+    assert_eq!(contract.owner_id(), accounts(0));
+    assert_eq!(contract.pending_owner_id(), Some(accounts(1)));
+
+    testing_env!(context.predecessor_account_id(accounts(1)).build());
+    contract.accept_ownership();
+    assert_eq!(contract.owner_id(), accounts(1));
+    assert_eq!(contract.pending_owner_id(), None);
+    contract.set_mint_price(U128(2 * MINT_PRICE));
+}
+
+#[test]
+fn test_ownership_transfer_revokes_roles() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 10, U128(MINT_PRICE), None, None);
+    contract.grant_role(Role::MetadataManager, accounts(2));
+    contract.grant_role(Role::Treasurer, accounts(3));
+    assert_eq!(
+        contract.roles(None, None),
+        vec![
+            RoleGrant { role: Role::MetadataManager, account_id: accounts(2) },
+            RoleGrant { role: Role::Treasurer, account_id: accounts(3) },
+        ]
+    );
+    assert_eq!(contract.roles(Some(U128(1)), Some(1)), vec![RoleGrant { role: Role::Treasurer, account_id: accounts(3) }]);
+
+    //the new owner starts without the delegates of the previous one
+    contract.propose_owner(Some(accounts(1)));
+    testing_env!(context.predecessor_account_id(accounts(1)).build());
+    contract.accept_ownership();
+    assert!(contract.roles(None, None).is_empty());
+    assert!(!contract.has_role(Role::MetadataManager, accounts(2)));
+}
+
+#[test]
+#[should_panic(expected = "Only the proposed owner can accept the ownership")]
+fn test_accept_ownership_not_proposed_owner() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 10, U128(MINT_PRICE), None, None);
+    contract.propose_owner(Some(accounts(1)));
+
+    testing_env!(context.predecessor_account_id(accounts(2)).build());
+    contract.accept_ownership();
+}
+
+#[test]
+fn test_roles() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 10, U128(MINT_PRICE), None, None);
+    contract.grant_role(Role::MetadataManager, accounts(1));
+    assert!(contract.has_role(Role::MetadataManager, accounts(1)));
+    assert!(!contract.has_role(Role::SaleManager, accounts(1)));
+
+    //the metadata manager can upload metadata and publish the collection
+    testing_env!(context.predecessor_account_id(accounts(1)).build());
+    contract.append_encrypted_metadata(SAMPLE_ENCRYPTED_METADATA.to_string());
+    contract.set_provenance(vec![sample_metadata_hash()]);
+    contract.commit_reveal_key(sample_reveal_key_hash(&contract));
+    contract.set_collection_state(CollectionState::Published);
+
+    testing_env!(context.predecessor_account_id(accounts(0)).build());
+    contract.revoke_role(Role::MetadataManager, accounts(1));
+    assert!(!contract.has_role(Role::MetadataManager, accounts(1)));
+}
+
+#[test]
+#[should_panic(expected = "Requires the owner or the SaleManager role")]
+fn test_role_required() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 10, U128(MINT_PRICE), None, None);
+    contract.grant_role(Role::MetadataManager, accounts(1));
+
+    testing_env!(context.predecessor_account_id(accounts(1)).build());
+    contract.set_mint_price(U128(2 * MINT_PRICE));
+}
+
+#[test]
+#[should_panic(expected = "owner_id should be sender_id")]
+fn test_grant_role_not_owner() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 10, U128(MINT_PRICE), None, None);
+
+    testing_env!(context.predecessor_account_id(accounts(1)).build());
+    contract.grant_role(Role::SaleManager, accounts(1));
+}
+
+fn get_paused_contract(context: &mut VMContextBuilder, pause_flags: PauseFlags) -> (Contract, TokenId) {
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 10, U128(MINT_PRICE), None, None);
+    contract.set_collection_state(CollectionState::Published);
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(0))
+        .build());
+    let token_id = contract.nft_mint(accounts(0), None);
+    contract.set_pause_flags(pause_flags);
+    (contract, token_id)
+}
+
+#[test]
+fn test_set_pause_flags() {
+    let mut context = get_context(accounts(0));
+    let pause_flags = PauseFlags { transfer: true, ..Default::default() };
+    let (contract, _) = get_paused_contract(&mut context, pause_flags.clone());
+    assert_eq!(contract.pause_flags(), pause_flags);
+    assert_eq!(
+        near_sdk::test_utils::get_logs().last().unwrap(),
+        r#"EVENT_JSON:{"standard":"pray","version":"1.0.0","event":"pause_flags_update","data":[{"mint":false,"transfer":true,"approve":false,"move_character":false}]}"#
+    );
+}
+
+#[test]
+#[should_panic(expected = "owner_id should be sender_id")]
+fn test_set_pause_flags_not_owner() {
+    let mut context = get_context(accounts(0));
+    let (mut contract, _) = get_paused_contract(&mut context, PauseFlags::default());
+
+    testing_env!(context.predecessor_account_id(accounts(1)).build());
+    contract.set_pause_flags(PauseFlags { mint: true, ..Default::default() });
+}
+
+#[test]
+#[should_panic(expected = "Minting is paused")]
+fn test_mint_paused() {
+    let mut context = get_context(accounts(0));
+    let (mut contract, _) = get_paused_contract(&mut context, PauseFlags { mint: true, ..Default::default() });
+    contract.nft_mint(accounts(0), None);
+}
+
+#[test]
+#[should_panic(expected = "Transfers are paused")]
+fn test_transfer_paused() {
+    let mut context = get_context(accounts(0));
+    let (mut contract, token_id) = get_paused_contract(&mut context, PauseFlags { transfer: true, ..Default::default() });
+    contract.internal_transfer(&accounts(0), &accounts(1), &token_id, None, None);
+}
+
+#[test]
+#[should_panic(expected = "Transfers are paused")]
+fn test_burn_paused() {
+    let mut context = get_context(accounts(0));
+    let (mut contract, token_id) = get_paused_contract(&mut context, PauseFlags { transfer: true, ..Default::default() });
+
+    testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(0)).build());
+    contract.nft_burn(token_id, None, None);
+}
+
+#[test]
+#[should_panic(expected = "Approvals are paused")]
+fn test_approve_paused() {
+    let mut context = get_context(accounts(0));
+    let (mut contract, token_id) = get_paused_contract(&mut context, PauseFlags { approve: true, ..Default::default() });
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MIN_REQUIRED_APPROVAL_YOCTO)
+        .predecessor_account_id(accounts(0))
+        .build());
+    contract.nft_approve(token_id, accounts(1), None);
+}
+
+/*
+    write a frozen storage snapshot, as pairs of base64 keys and values.
+    `state_v1.json` was written by the contract before its state was versioned: 2 of 5 tokens minted sequentially to bob,
+    published with the legacy metadata batch of token "0" left to reveal. `state_v2.json` was written by the current layout.
+*/
+fn load_state_fixture(fixture: &str) {
+    let entries: Vec<(Base64VecU8, Base64VecU8)> = near_sdk::serde_json::from_str(fixture).unwrap();
+    for (key, value) in entries {
+        env::storage_write(&key.0, &value.0);
+    }
+}
+
+//migrate the state of version 1 into the location accounts(3), with the commitments of its metadata batch
+fn migrate_state_v1(present_characters: HashMap<AccountId, Vec<TokenId>>) -> Contract {
+    load_state_fixture(include_str!("fixtures/state_v1.json"));
+    let reveal_key = DerivedKey::derive("password", kdf_params(SAMPLE_ENCRYPTED_METADATA).unwrap()).unwrap();
+    Contract::migrate(
+        Some(vec![sample_location(accounts(3), "Abandoned Ruins")]),
+        Some(present_characters),
+        Some(vec![sample_metadata_hash()]),
+        Some(Base64VecU8(env::sha256(&reveal_key.key))),
+    )
+}
+
+#[test]
+fn test_migrate_from_v1() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+
+    let mut contract = migrate_state_v1(HashMap::new());
+    assert_eq!(contract.state_version(), STATE_VERSION);
+    assert_eq!(contract.owner_id(), accounts(0));
+    assert_eq!(contract.nft_total_supply(), U128(2));
+    assert_eq!(contract.nft_supply_for_owner(accounts(1)), U128(2));
+    assert_eq!(contract.metadata.get().unwrap().name, "Pray");
+    assert_eq!(contract.collection_state, CollectionState::Published);
+    assert_eq!(contract.reveal_progress(), RevealProgress { revealed_batches: 0, remaining_batches: 1 });
+    assert_eq!(contract.mint_price(), U128(0));
+    assert!(contract.allow_legacy_kdf());
+    assert!(contract.location(accounts(3)).unwrap().enabled);
+
+    //the rest of the collection is minted without reusing the sequentially minted ids
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(3 * MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(2))
+        .build());
+    let mut token_ids = contract.nft_batch_mint(accounts(2), 3, None);
+    token_ids.sort();
+    assert_eq!(token_ids, vec!["2", "3", "4"]);
+}
+
+//a change of the layout breaks this test, and needs a new version with its own fixture and migration
+#[test]
+fn test_state_v2_fixture() {
+    let context = get_context(accounts(0));
+    testing_env!(context.build());
+    load_state_fixture(include_str!("fixtures/state_v2.json"));
+
+    let contract: Contract = env::state_read().unwrap();
+    assert_eq!(contract.state_version(), STATE_VERSION);
+    assert_eq!(contract.owner_id(), accounts(0));
+    assert_eq!(contract.nft_tokens(None, None)[0].owner_id, accounts(1));
+    assert_eq!(contract.locations(None, None).len(), 1);
+    assert_eq!(contract.character_location("1".to_string()), Some(accounts(3)));
+    assert_eq!(contract.characters_in_location(accounts(3)), 1);
+    assert_eq!(contract.roles(None, None), vec![RoleGrant { role: Role::Revealer, account_id: accounts(2) }]);
+    assert!(contract.force_leave_on_transfer());
+    assert_eq!(contract.character_stats("1".to_string()).unwrap().class, Some("Miner".to_string()));
+}
+
+#[test]
+#[should_panic(expected = "State version 2 can't be migrated to version 2")]
+fn test_migrate_current_state() {
+    let context = get_context(accounts(0));
+    testing_env!(context.build());
+    let contract = Contract::new_default_meta(accounts(0), 5, U128(MINT_PRICE), None, None);
+    env::state_write(&contract);
+    Contract::migrate(None, None, None, None);
+}
+
+//the published collection is revealed with the commitments given to the migration
+#[test]
+fn test_migrate_from_v1_reveal() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = migrate_state_v1(HashMap::new());
+
+    testing_env!(context.storage_usage(env::storage_usage()).build());
+    contract.submit_reveal_key("password".to_string());
+    assert!(contract.reveal());
+    assert_eq!(contract.reveal_progress(), RevealProgress { revealed_batches: 1, remaining_batches: 0 });
+    assert_eq!(contract.nft_tokens(None, None)[0].metadata.title, Some("Villager 0".to_string()));
+}
+
+#[test]
+#[should_panic(expected = "The provenance and the reveal key commitment of the metadata left to reveal are required to migrate a published collection")]
+fn test_migrate_from_v1_without_provenance() {
+    let context = get_context(accounts(0));
+    testing_env!(context.build());
+    load_state_fixture(include_str!("fixtures/state_v1.json"));
+    Contract::migrate(Some(vec![]), Some(HashMap::new()), None, None);
+}
+
+//the characters the location migrates are in it for the character contract as well
+#[test]
+fn test_migrate_from_v1_present_characters() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = migrate_state_v1(HashMap::from([(accounts(3), vec!["0".to_string()])]));
+    assert_eq!(contract.character_location("0".to_string()), Some(accounts(3)));
+    assert_eq!(contract.characters_in_location(accounts(3)), 1);
+    assert_eq!(contract.transfer_lock("0".to_string()), Some(TransferLock::InLocation { location_id: accounts(3) }));
+    assert_eq!(contract.character_location("1".to_string()), None);
+
+    //so the character can leave it, and only then enter another location
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .predecessor_account_id(accounts(3))
+        .signer_account_id(accounts(1))
+        .build());
+    assert!(contract.move_character("0".to_string(), None));
+    assert_eq!(contract.character_location("0".to_string()), None);
+    assert_eq!(contract.characters_in_location(accounts(3)), 0);
+}
+
+#[test]
+#[should_panic(expected = "Character 0 is already in")]
+fn test_migrate_from_v1_character_in_two_locations() {
+    let context = get_context(accounts(0));
+    testing_env!(context.build());
+    load_state_fixture(include_str!("fixtures/state_v1.json"));
+    Contract::migrate(
+        Some(vec![sample_location(accounts(3), "Abandoned Ruins"), sample_location(accounts(4), "Sunken Temple")]),
+        Some(HashMap::from([(accounts(3), vec!["0".to_string()]), (accounts(4), vec!["0".to_string()])])),
+        Some(vec![sample_metadata_hash()]),
+        Some(sample_metadata_hash()),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Location eugene isn't registered")]
+fn test_migrate_from_v1_unregistered_location() {
+    let context = get_context(accounts(0));
+    testing_env!(context.build());
+    load_state_fixture(include_str!("fixtures/state_v1.json"));
+    Contract::migrate(
+        Some(vec![sample_location(accounts(3), "Abandoned Ruins")]),
+        Some(HashMap::from([(accounts(4), vec!["0".to_string()])])),
+        Some(vec![sample_metadata_hash()]),
+        Some(sample_metadata_hash()),
+    );
+}
+
+#[test]
+#[should_panic(expected = "At most 100 present characters can be migrated")]
+fn test_migrate_from_v1_too_many_characters() {
+    let context = get_context(accounts(0));
+    testing_env!(context.build());
+    load_state_fixture(include_str!("fixtures/state_v1.json"));
+    let character_ids = (0..=MAX_MIGRATED_CHARACTERS).map(|character_id| character_id.to_string()).collect();
+    Contract::migrate(
+        Some(vec![sample_location(accounts(3), "Abandoned Ruins")]),
+        Some(HashMap::from([(accounts(3), character_ids)])),
+        Some(vec![sample_metadata_hash()]),
+        Some(sample_metadata_hash()),
+    );
+}
+
+#[test]
+fn test_drop_state() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_published_release_contract(&mut context);
+    contract.submit_reveal_key("password".to_string());
+    contract.reveal();
+    contract.grant_role(Role::Revealer, accounts(1));
+    contract.sale_allowlist.insert(&("presale".to_string(), accounts(1)));
+    contract.sale_minted_per_account.insert(&("presale".to_string(), accounts(1)), &1);
+    contract.allowlist_minted.insert(&accounts(1), &1);
+
+    //every call removes at most 2 entries
+    let mut calls = 1;
+    while contract.drop_state(Some(2), Some(true)) {
+        calls += 1;
+    }
+    assert_eq!(calls, 4);
+
+    assert_eq!(contract.nft_total_supply(), U128(0));
+    assert!(contract.tokens_per_owner.get(&accounts(0)).is_none());
+    assert!(contract.token_metadata_by_id.is_empty());
+    assert!(contract.encrypted_metadata.is_empty());
+    assert!(contract.sale_allowlist.is_empty());
+    assert!(contract.sale_minted_per_account.is_empty());
+    assert!(contract.allowlist_minted.is_empty());
+    assert!(contract.roles.is_empty());
+    assert!(contract.metadata.get().is_none());
+    assert_eq!(contract.revealed_batches, 0);
+}
+
+#[test]
+fn test_drop_state_unminted_token_ids() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 10, U128(MINT_PRICE), None, None);
+    contract.set_collection_state(CollectionState::Published);
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(3 * (MINT_PRICE + MINT_STORAGE_COST))
+        .predecessor_account_id(accounts(1))
+        .build());
+    contract.nft_batch_mint(accounts(1), 3, None);
+
+    testing_env!(context.predecessor_account_id(accounts(0)).build());
+    while contract.drop_state(None, Some(true)) {}
+    assert!((0..10).all(|position| contract.unminted_token_ids.get(&position).is_none()));
+    assert!(contract.tokens_per_owner.get(&accounts(1)).is_none());
+}
+
+#[test]
+#[should_panic(expected = "Can't drop the state of a published collection without force")]
+fn test_drop_state_published() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_published_release_contract(&mut context);
+    contract.drop_state(None, None);
+}
+
+fn sample_location(location_id: AccountId, name: &str) -> JsonLocation {
+    JsonLocation {
+        location_id,
+        metadata: LocationMetadata {
+            name: name.to_string(),
+            coordinates: Coordinates { x: 1, y: -2 },
+            enabled: true,
+        },
+    }
+}
+
+#[test]
+fn test_locations() {
+    let context = get_context(accounts(0));
+    testing_env!(context.build());
+    let ruins = sample_location(accounts(3), "Abandoned Ruins");
+    let mut contract = Contract::new_default_meta(accounts(0), 10, U128(MINT_PRICE), None, Some(vec![ruins.clone()]));
+    assert_eq!(contract.location(accounts(3)), Some(ruins.metadata.clone()));
+
+    let forest = sample_location(accounts(4), "Dark Forest");
+    contract.register_location(forest.location_id.clone(), forest.metadata.clone());
+    assert_eq!(contract.locations(None, None), vec![ruins.clone(), forest.clone()]);
+    assert_eq!(contract.locations(Some(U128(1)), Some(1)), vec![forest.clone()]);
+
+    contract.set_location_enabled(accounts(4), false);
+    assert!(!contract.location(accounts(4)).unwrap().enabled);
+
+    contract.unregister_location(accounts(3));
+    assert_eq!(contract.location(accounts(3)), None);
+    assert_eq!(contract.locations(None, None).len(), 1);
+}
+
+#[test]
+#[should_panic(expected = "owner_id should be sender_id")]
+fn test_register_location_not_owner() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 10, U128(MINT_PRICE), None, None);
+
+    testing_env!(context.predecessor_account_id(accounts(3)).build());
+    let location = sample_location(accounts(3), "Abandoned Ruins");
+    contract.register_location(location.location_id, location.metadata);
+}
+
+fn get_location_contract(context: &mut VMContextBuilder) -> (Contract, TokenId) {
+    testing_env!(context.build());
+    let location = sample_location(accounts(3), "Abandoned Ruins");
+    let mut contract = Contract::new_default_meta(accounts(0), 10, U128(MINT_PRICE), None, Some(vec![location]));
+    contract.set_collection_state(CollectionState::Published);
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(1))
+        .build());
+    let token_id = contract.nft_mint(accounts(1), None);
+    (contract, token_id)
+}
+
+#[test]
+fn test_move_character() {
+    let mut context = get_context(accounts(0));
+    let (mut contract, token_id) = get_location_contract(&mut context);
+
+    testing_env!(context.predecessor_account_id(accounts(3)).signer_account_id(accounts(1)).build());
+    assert!(contract.move_character(token_id.clone(), Some("Abandoned Ruins".to_string())));
+    assert_eq!(contract.character_location(token_id.clone()), Some(accounts(3)));
+    assert_eq!(contract.nft_tokens(None, None)[0].location, Some(accounts(3)));
+    assert_eq!(near_sdk::test_utils::get_logs(), vec![format!("Character {} entered Abandoned Ruins", token_id)]);
+
+    assert!(contract.move_character(token_id.clone(), None));
+    assert_eq!(contract.character_location(token_id.clone()), None);
+    assert_eq!(contract.nft_tokens(None, None)[0].location, None);
+}
+
+#[test]
+#[should_panic(expected = "Character is already in danny")]
+fn test_move_character_enter_twice() {
+    let mut context = get_context(accounts(0));
+    let (mut contract, token_id) = get_location_contract(&mut context);
+
+    testing_env!(context.predecessor_account_id(accounts(0)).build());
+    let forest = sample_location(accounts(4), "Dark Forest");
+    contract.register_location(forest.location_id, forest.metadata);
+
+    testing_env!(context.predecessor_account_id(accounts(3)).signer_account_id(accounts(1)).build());
+    contract.move_character(token_id.clone(), Some("Abandoned Ruins".to_string()));
+
+    testing_env!(context.predecessor_account_id(accounts(4)).signer_account_id(accounts(1)).build());
+    contract.move_character(token_id, Some("Dark Forest".to_string()));
+}
+
+#[test]
+#[should_panic(expected = "Character is not in this location")]
+fn test_move_character_leave_other_location() {
+    let mut context = get_context(accounts(0));
+    let (mut contract, token_id) = get_location_contract(&mut context);
+
+    testing_env!(context.predecessor_account_id(accounts(0)).build());
+    let forest = sample_location(accounts(4), "Dark Forest");
+    contract.register_location(forest.location_id, forest.metadata);
+
+    testing_env!(context.predecessor_account_id(accounts(3)).signer_account_id(accounts(1)).build());
+    contract.move_character(token_id.clone(), Some("Abandoned Ruins".to_string()));
+
+    testing_env!(context.predecessor_account_id(accounts(4)).signer_account_id(accounts(1)).build());
+    contract.move_character(token_id, None);
+}
+
+#[test]
+#[should_panic(expected = "Character is not in this location")]
+fn test_move_character_leave_without_entering() {
+    let mut context = get_context(accounts(0));
+    let (mut contract, token_id) = get_location_contract(&mut context);
+
+    testing_env!(context.predecessor_account_id(accounts(3)).signer_account_id(accounts(1)).build());
+    contract.move_character(token_id, None);
+}
+
+#[test]
+#[should_panic(expected = "Character must leave its location before it is burned")]
+fn test_nft_burn_in_location() {
+    let mut context = get_context(accounts(0));
+    let (mut contract, token_id) = get_location_contract(&mut context);
+
+    testing_env!(context.predecessor_account_id(accounts(3)).signer_account_id(accounts(1)).build());
+    contract.move_character(token_id.clone(), Some("Abandoned Ruins".to_string()));
+
+    testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(1).build());
+    contract.nft_burn(token_id, None, None);
+}
+
+#[test]
+#[should_panic(expected = "Can only be called by a registered location contract")]
+fn test_move_character_unregistered_location() {
+    let mut context = get_context(accounts(0));
+    let (mut contract, token_id) = get_location_contract(&mut context);
+
+    testing_env!(context.predecessor_account_id(accounts(4)).signer_account_id(accounts(1)).build());
+    contract.move_character(token_id, Some("Abandoned Ruins".to_string()));
+}
+
+#[test]
+#[should_panic(expected = "Location is disabled")]
+fn test_move_character_disabled_location() {
+    let mut context = get_context(accounts(0));
+    let (mut contract, token_id) = get_location_contract(&mut context);
+
+    testing_env!(context.predecessor_account_id(accounts(0)).build());
+    contract.set_location_enabled(accounts(3), false);
+
+    testing_env!(context.predecessor_account_id(accounts(3)).signer_account_id(accounts(1)).build());
+    contract.move_character(token_id, Some("Abandoned Ruins".to_string()));
+}
+
+#[test]
+fn test_leave_disabled_location() {
+    let mut context = get_context(accounts(0));
+    let (mut contract, token_id) = get_location_contract(&mut context);
+
+    testing_env!(context.predecessor_account_id(accounts(3)).signer_account_id(accounts(1)).build());
+    contract.move_character(token_id.clone(), Some("Abandoned Ruins".to_string()));
+    assert_eq!(contract.characters_in_location(accounts(3)), 1);
+
+    testing_env!(context.predecessor_account_id(accounts(0)).build());
+    contract.set_location_enabled(accounts(3), false);
+
+    testing_env!(context.predecessor_account_id(accounts(3)).signer_account_id(accounts(1)).build());
+    contract.move_character(token_id.clone(), None);
+    assert_eq!(contract.character_location(token_id), None);
+    assert_eq!(contract.characters_in_location(accounts(3)), 0);
+
+    //once empty, the location can be unregistered
+    testing_env!(context.predecessor_account_id(accounts(0)).build());
+    contract.unregister_location(accounts(3));
+    assert_eq!(contract.location(accounts(3)), None);
+}
+
+#[test]
+#[should_panic(expected = "Characters are still in the location")]
+fn test_unregister_occupied_location() {
+    let mut context = get_context(accounts(0));
+    let (mut contract, token_id) = get_location_contract(&mut context);
+
+    testing_env!(context.predecessor_account_id(accounts(3)).signer_account_id(accounts(1)).build());
+    contract.move_character(token_id, Some("Abandoned Ruins".to_string()));
+
+    testing_env!(context.predecessor_account_id(accounts(0)).build());
+    contract.unregister_location(accounts(3));
+}
+
+#[test]
+#[should_panic(expected = "Character is locked in danny")]
+fn test_transfer_in_location() {
+    let mut context = get_context(accounts(0));
+    let (mut contract, token_id) = get_location_contract(&mut context);
+
+    testing_env!(context.predecessor_account_id(accounts(3)).signer_account_id(accounts(1)).build());
+    contract.move_character(token_id.clone(), Some("Abandoned Ruins".to_string()));
+    assert_eq!(contract.transfer_lock(token_id.clone()), Some(TransferLock::InLocation { location_id: accounts(3) }));
+
+    contract.internal_transfer(&accounts(1), &accounts(2), &token_id, None, None);
+}
+
+#[test]
+fn test_transfer_force_leave() {
+    let mut context = get_context(accounts(0));
+    let (mut contract, token_id) = get_location_contract(&mut context);
+
+    testing_env!(context.predecessor_account_id(accounts(3)).signer_account_id(accounts(1)).build());
+    contract.move_character(token_id.clone(), Some("Abandoned Ruins".to_string()));
+
+    testing_env!(context.predecessor_account_id(accounts(0)).build());
+    contract.set_force_leave_on_transfer(true);
+    assert_eq!(contract.transfer_lock(token_id.clone()), None);
+
+    contract.internal_transfer(&accounts(1), &accounts(2), &token_id, None, None);
+    assert_eq!(contract.character_location(token_id.clone()), None);
+    assert_eq!(contract.nft_tokens(None, None)[0].owner_id, accounts(2));
+    assert_eq!(
+        near_sdk::test_utils::get_logs()[0],
+        format!("Character {} was forced to leave {}", token_id, accounts(3))
+    );
+}
+
+#[test]
+fn test_force_leave() {
+    let mut context = get_context(accounts(0));
+    let (mut contract, token_id) = get_location_contract(&mut context);
+    assert_eq!(contract.transfer_lock(token_id.clone()), None);
+
+    testing_env!(context.predecessor_account_id(accounts(3)).signer_account_id(accounts(1)).build());
+    contract.move_character(token_id.clone(), Some("Abandoned Ruins".to_string()));
+
+    testing_env!(context.predecessor_account_id(accounts(0)).build());
+    contract.set_pause_flags(PauseFlags { transfer: true, ..Default::default() });
+    assert_eq!(contract.transfer_lock(token_id.clone()), Some(TransferLock::TransfersPaused));
+    contract.set_pause_flags(PauseFlags::default());
+
+    contract.force_leave(token_id.clone());
+    assert_eq!(contract.character_location(token_id.clone()), None);
+    assert_eq!(contract.characters_in_location(accounts(3)), 0);
+    assert_eq!(contract.transfer_lock(token_id.clone()), None);
+
+    //the location is told that the character left
+    let receipts = near_sdk::test_utils::get_created_receipts();
+    assert_eq!(receipts.len(), 1);
+    assert_eq!(receipts[0].receiver_id, accounts(3));
+    assert!(matches!(
+        &receipts[0].actions[0],
+        near_sdk::mock::VmAction::FunctionCall { function_name, args, .. }
+            if function_name == "on_force_leave" && args == format!(r#"{{"character_id":"{}"}}"#, token_id).as_bytes()
+    ));
+}
+
+fn metadata_with_extra(extra: &str) -> TokenMetadata {
+    near_sdk::serde_json::from_value(near_sdk::serde_json::json!({ "title": "Villager 0", "extra": extra })).unwrap()
+}
+
+#[test]
+fn test_character_stats_from_metadata() {
+    let metadata = metadata_with_extra(
+        r#"{"attributes":[{"trait_type":"Class","value":"Miner"},{"trait_type":"Strength","value":7},{"trait_type":"Hair","value":"Red"},{"trait_type":"Luck","value":-1}]}"#,
+    );
+    let character_stats = CharacterStats::from_metadata(&metadata).unwrap();
+    assert_eq!(character_stats.class, Some("Miner".to_string()));
+    assert_eq!(character_stats.stats.into_iter().collect::<Vec<_>>(), vec![("Strength".to_string(), 7)]);
+
+    assert_eq!(CharacterStats::from_metadata(&metadata_with_extra("not json")), None);
+    assert_eq!(CharacterStats::from_metadata(&metadata_with_extra("{}")), None);
+}
+
+#[test]
+fn test_reveal_character_stats() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0), 1, U128(MINT_PRICE), None, None);
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_PRICE + MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(0))
+        .build());
+    let plaintext = near_sdk::serde_json::json!({
+        "0": { "title": "Villager 0", "extra": r#"{"attributes":[{"trait_type":"Class","value":"Miner"},{"trait_type":"Strength","value":7}]}"# }
+    }).to_string();
+    contract.append_encrypted_metadata(aes_gcm_encrypt("password", &plaintext, 1_000, &[1; 16], &[2; 12]).unwrap());
+    contract.set_provenance(vec![Base64VecU8(env::sha256(plaintext.as_bytes()))]);
+    contract.commit_reveal_key(sample_reveal_key_hash(&contract));
+    contract.set_collection_state(CollectionState::Published);
+    let token_id = contract.nft_mint(accounts(0), None);
+    assert_eq!(contract.character_stats(token_id.clone()), None);
+
+    contract.submit_reveal_key("password".to_string());
+    contract.reveal();
+    let character_stats = contract.character_stats(token_id.clone()).unwrap();
+    assert_eq!(character_stats.class, Some("Miner".to_string()));
+    assert_eq!(character_stats.stats.get("Strength"), Some(&7));
+    assert_eq!(contract.character_stats.get(&token_id), Some(character_stats));
+    assert_eq!(contract.character_stats("1".to_string()), None);
+}
+
+#[test]
+fn test_lazy_reveal_character_stats() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_lazy_release_contract(&mut context, 3);
+    contract.submit_reveal_key("password".to_string());
+
+    //the view doesn't decrypt, so the stats of lazily revealed characters are stored first by anyone
+    assert_eq!(contract.character_stats("0".to_string()), None);
+    testing_env!(context.predecessor_account_id(accounts(2)).attached_deposit(0).build());
+    assert_eq!(contract.cache_character_stats(vec!["0".to_string(), "1".to_string(), "5".to_string()]), 2);
+    assert_eq!(contract.cache_character_stats(vec!["0".to_string()]), 0);
+
+    let character_stats = contract.character_stats("0".to_string()).unwrap();
+    assert_eq!(character_stats.class, Some("Miner".to_string()));
+    assert_eq!(character_stats.stats.get("Strength"), Some(&7));
+    assert_eq!(contract.character_stats("1".to_string()), Some(character_stats));
+    assert_eq!(contract.character_stats("2".to_string()), None);
+}
+
+#[test]
+#[should_panic(expected = "Reveal key wasn't submitted")]
+fn test_cache_character_stats_before_reveal() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_lazy_release_contract(&mut context, 1);
+    contract.cache_character_stats(vec!["0".to_string()]);
+}
+
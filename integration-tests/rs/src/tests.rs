@@ -6,9 +6,148 @@ use workspaces::{network::Sandbox, Account, Contract, Worker};
 mod helpers;
 
 const CHARACTER_WASM_FILEPATH: &str = "../../out/character.wasm";
+const LOCATION_WASM_FILEPATH: &str = "../../out/location.wasm";
+const RESOURCE_WASM_FILEPATH: &str = "../../out/resource.wasm";
+// gas the location contract attaches to `move_character`, `GAS_FOR_CHARACTER_MOVE` in location-contract/src/lib.rs
+const GAS_FOR_CHARACTER_MOVE: u64 = 10_000_000_000_000;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Add tests here
+    let worker = workspaces::sandbox().await?;
+
+    // deploy the contracts, each of which has to know the others when it is initialized
+    let character_contract = worker.dev_deploy(&std::fs::read(CHARACTER_WASM_FILEPATH)?).await?;
+    let location_contract = worker.dev_deploy(&std::fs::read(LOCATION_WASM_FILEPATH)?).await?;
+    let resource_contract = worker.dev_deploy(&std::fs::read(RESOURCE_WASM_FILEPATH)?).await?;
+
+    let owner = worker.dev_create_account().await?;
+    let alice = worker.dev_create_account().await?;
+    let bob = worker.dev_create_account().await?;
+
+    character_contract
+        .call(&worker, "new_default_meta")
+        .args_json(json!({
+            "owner_id": owner.id(),
+            "collection_size": 10,
+            "mint_price": "1",
+            "locations": [{
+                "location_id": location_contract.id(),
+                "metadata": {"name": "Abandoned Ruins", "coordinates": {"x": 0, "y": 0}, "enabled": true}
+            }]
+        }))?
+        .transact()
+        .await?;
+    location_contract
+        .call(&worker, "new")
+        .args_json(json!({
+            "owner_id": owner.id(),
+            "name": "Abandoned Ruins",
+            "yields": [{"resource_id": resource_contract.id(), "rate": 1}],
+            "character_contract_id": character_contract.id()
+        }))?
+        .transact()
+        .await?;
+    resource_contract
+        .call(&worker, "new_default_meta")
+        .args_json(json!({"owner_id": owner.id(), "locations": [location_contract.id()]}))?
+        .transact()
+        .await?;
+
+    // begin tests
+    test_transfer_claim(&owner, &alice, &bob, &character_contract, &location_contract, &resource_contract, &worker).await?;
+    Ok(())
+}
+
+// once a character is transferred while inside a location, its previous owner can no longer claim with it
+async fn test_transfer_claim(
+    owner: &Account,
+    alice: &Account,
+    bob: &Account,
+    character_contract: &Contract,
+    location_contract: &Contract,
+    resource_contract: &Contract,
+    worker: &Worker<Sandbox>,
+) -> anyhow::Result<()> {
+    owner.call(&worker, character_contract.id(), "set_collection_state")
+        .args_json(json!({"collection_state": "Published"}))?
+        .transact()
+        .await?;
+    owner.call(&worker, character_contract.id(), "set_force_leave_on_transfer")
+        .args_json(json!({"force_leave_on_transfer": true}))?
+        .transact()
+        .await?;
+
+    let token_id: String = alice.call(&worker, character_contract.id(), "nft_mint")
+        .args_json(json!({"receiver_id": alice.id()}))?
+        .deposit(parse_near!("1 N"))
+        .gas(helpers::DEFAULT_GAS as u64)
+        .transact()
+        .await?
+        .json()?;
+    for account in [alice, bob] {
+        account.call(&worker, resource_contract.id(), "storage_deposit")
+            .args_json(json!({}))?
+            .deposit(parse_near!("0.00125 N"))
+            .transact()
+            .await?;
+    }
+
+    let outcome = alice.call(&worker, location_contract.id(), "enter")
+        .args_json(json!({"character_id": token_id}))?
+        .gas(helpers::DEFAULT_GAS as u64)
+        .transact()
+        .await?;
+    assert!(outcome.is_success());
+
+    // the move burns well under the gas the location attaches to it
+    let move_outcome = outcome
+        .receipt_outcomes()
+        .iter()
+        .find(|receipt| receipt.executor_id == *character_contract.id() && receipt.logs.iter().any(|log| log.contains("entered")))
+        .expect("move_character wasn't called");
+    assert!(move_outcome.gas_burnt < GAS_FOR_CHARACTER_MOVE / 2);
+
+    // the transfer forces the character out of the location, which settles what it accrued for alice
+    helpers::transfer_nft(alice, bob, character_contract, worker, &token_id).await?;
+    let pending_yield: serde_json::Value = location_contract
+        .view(&worker, "pending_yield", json!({"character_id": token_id}).to_string().into_bytes())
+        .await?
+        .json()?;
+    assert_eq!(pending_yield, serde_json::Value::Null);
+
+    // alice can't claim with the character anymore, but keeps what it accrued while it was hers
+    let outcome = alice.call(&worker, location_contract.id(), "claim")
+        .args_json(json!({"character_id": token_id}))?
+        .gas(helpers::DEFAULT_GAS as u64)
+        .transact()
+        .await?;
+    assert!(!outcome.is_success());
+
+    let outcome = alice.call(&worker, location_contract.id(), "claim_unminted")
+        .args_json(json!({}))?
+        .gas(helpers::DEFAULT_GAS as u64)
+        .transact()
+        .await?;
+    assert!(outcome.is_success());
+    let balance: String = resource_contract
+        .view(&worker, "ft_balance_of", json!({"account_id": alice.id()}).to_string().into_bytes())
+        .await?
+        .json()?;
+    assert!(balance.parse::<u128>()? > 0);
+
+    // the new owner can take the character back in
+    let outcome = bob.call(&worker, location_contract.id(), "enter")
+        .args_json(json!({"character_id": token_id}))?
+        .gas(helpers::DEFAULT_GAS as u64)
+        .transact()
+        .await?;
+    assert!(outcome.is_success());
+    let character_location: Option<String> = character_contract
+        .view(&worker, "character_location", json!({"token_id": token_id}).to_string().into_bytes())
+        .await?
+        .json()?;
+    assert_eq!(character_location.as_deref(), Some(location_contract.id().as_str()));
+
+    println!("      Passed ✅ test_transfer_claim");
     Ok(())
 }
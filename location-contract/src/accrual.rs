@@ -0,0 +1,242 @@
+use crate::*;
+
+/*
+    every resource of the location accrues at its own rate per block for every character present in the location.
+    `per_character` is what a character present since the resource was added would have accrued by `yield_updated_at_block`,
+    and each character keeps the values it had when it last entered or claimed, so that its pending yield is the difference.
+    the accrual is brought up to date before every change of the yields, which keeps a new rate from applying to past blocks.
+    what a character accrues is scaled by the multiplier of its traits.
+*/
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ResourceYield {
+    pub resource_id: AccountId, // Resource token contract the resource is minted into
+    pub rate: u32, // Resources accrued per block by every present character
+    pub per_character: u128, // Resources accrued by a character present since the resource was added
+}
+
+// Entry of the yield table
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ResourceRate {
+    pub resource_id: AccountId,
+    pub rate: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ResourceAmount {
+    pub resource_id: AccountId,
+    pub amount: U128,
+}
+
+#[near_bindgen]
+impl Contract {
+    // Adds a resource to the yield table or changes its rate, a rate of 0 stops the accrual but keeps what is pending claimable
+    pub fn set_yield(&mut self, resource_id: AccountId, rate: u32) {
+        self.assert_called_by_owner();
+        self.internal_set_yield(resource_id, rate);
+    }
+
+    pub fn yields(&self) -> Vec<ResourceRate> {
+        self.yields
+            .iter()
+            .map(|resource| ResourceRate { resource_id: resource.resource_id.clone(), rate: resource.rate })
+            .collect()
+    }
+
+    // Mints the resources the character accrued to its owner and restarts the accrual, once the character contract confirms it is still here
+    pub fn claim(&mut self, character_id: TokenId) -> PromiseOrValue<Vec<ResourceAmount>> {
+        let character = self.present_characters.get(&character_id).expect("Character is not in this location");
+        assert_eq!(
+            character.owner,
+            env::predecessor_account_id(),
+            "Only the owner of the character can claim"
+        );
+
+        ext_character::ext(self.character_contract_id.clone())
+            .with_static_gas(GAS_FOR_CHARACTER_LOCATION)
+            .character_location(character_id.clone())
+        .then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(self.gas_for_resolve_claim())
+                .resolve_claim(
+                    character.owner,
+                    character_id
+                )
+        ).into()
+    }
+
+    // Settles the accrual of a character the character contract forced to leave, e.g. when it was transferred
+    pub fn on_force_leave(&mut self, character_id: TokenId) -> bool {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.character_contract_id,
+            "Can only be called by the character contract"
+        );
+        self.internal_remove_character(&character_id)
+    }
+
+    // Removes a character that is no longer in the location, keeping what it accrued for its owner
+    pub fn evict(&mut self, character_id: TokenId) -> bool {
+        self.assert_called_by_owner();
+        self.internal_remove_character(&character_id)
+    }
+
+    // Mints the resources of the characters that left, and retries the mints that failed for them
+    pub fn claim_unminted(&mut self) -> PromiseOrValue<Vec<ResourceAmount>> {
+        let owner = env::predecessor_account_id();
+        let amounts = self.unminted(owner.clone());
+        if amounts.is_empty() {
+            return PromiseOrValue::Value(amounts);
+        }
+        self.unminted.remove(&owner);
+        self.internal_mint(owner, None, amounts).into()
+    }
+
+    // Resources the character accrued since it entered or last claimed, None if it isn't in the location
+    pub fn pending_yield(&self, character_id: TokenId) -> Option<Vec<ResourceAmount>> {
+        self.present_characters.get(&character_id).map(|character| {
+            let yield_per_character = self.current_yield_per_character();
+            self.yields
+                .iter()
+                .map(|resource| ResourceAmount {
+                    resource_id: resource.resource_id.clone(),
+                    amount: U128(internal_accrued(&yield_per_character, &character, &resource.resource_id)),
+                })
+                .collect()
+        })
+    }
+
+    pub fn unminted(&self, account_id: AccountId) -> Vec<ResourceAmount> {
+        let mut amounts: Vec<ResourceAmount> = self.unminted
+            .get(&account_id)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(resource_id, amount)| ResourceAmount { resource_id, amount: U128(amount) })
+            .collect();
+        amounts.sort_by(|a, b| a.resource_id.cmp(&b.resource_id));
+        amounts
+    }
+}
+
+// Resources the character accrued since its checkpoint, a resource added after it entered has no checkpoint and accrues from 0
+fn internal_accrued(yield_per_character: &HashMap<AccountId, u128>, character: &CharacterData, resource_id: &AccountId) -> u128 {
+    let accrued = yield_per_character.get(resource_id).copied().unwrap_or(0)
+        - character.yield_checkpoints.get(resource_id).copied().unwrap_or(0);
+    accrued * character.multiplier_bps as u128 / BASE_MULTIPLIER_BPS as u128
+        + character.unclaimed.get(resource_id).copied().unwrap_or(0)
+}
+
+impl Contract {
+    // Accrual of every resource up to the current block
+    pub(crate) fn current_yield_per_character(&self) -> HashMap<AccountId, u128> {
+        let blocks = (env::block_height() - self.yield_updated_at_block) as u128;
+        self.yields
+            .iter()
+            .map(|resource| (resource.resource_id.clone(), resource.per_character + blocks * resource.rate as u128))
+            .collect()
+    }
+
+    // Accrues the current rates up to the current block
+    pub(crate) fn internal_update_yield(&mut self) {
+        let blocks = (env::block_height() - self.yield_updated_at_block) as u128;
+        for resource in self.yields.iter_mut() {
+            resource.per_character += blocks * resource.rate as u128;
+        }
+        self.yield_updated_at_block = env::block_height();
+    }
+
+    pub(crate) fn internal_set_yield(&mut self, resource_id: AccountId, rate: u32) {
+        // The blocks before the change accrue at the previous rate
+        self.internal_update_yield();
+        match self.yields.iter_mut().find(|resource| resource.resource_id == resource_id) {
+            Some(resource) => resource.rate = rate,
+            None => self.yields.push(ResourceYield { resource_id, rate, per_character: 0 }),
+        }
+    }
+
+    // Moves the checkpoints of a present character to the current block and returns the resources it accrued until then
+    pub(crate) fn internal_settle(&mut self, character_id: &TokenId, mut character: CharacterData) -> Vec<ResourceAmount> {
+        self.internal_update_yield();
+        let yield_per_character = self.current_yield_per_character();
+        let amounts = self.yields
+            .iter()
+            .map(|resource| ResourceAmount {
+                resource_id: resource.resource_id.clone(),
+                amount: U128(internal_accrued(&yield_per_character, &character, &resource.resource_id)),
+            })
+            .filter(|amount| amount.amount.0 > 0)
+            .collect();
+        character.yield_checkpoints = yield_per_character;
+        character.unclaimed.clear();
+        self.present_characters.insert(character_id, &character);
+        amounts
+    }
+
+    // Settles a present character into the resources kept for its owner and removes it, false if it isn't in the location
+    pub(crate) fn internal_remove_character(&mut self, character_id: &TokenId) -> bool {
+        match self.present_characters.get(character_id) {
+            Some(character) => {
+                let owner = character.owner.clone();
+                for amount in self.internal_settle(character_id, character) {
+                    self.internal_add_unminted(&owner, amount);
+                }
+                self.present_characters.remove(character_id);
+                true
+            }
+            None => false
+        }
+    }
+
+    // The callback of a claim mints every resource of the yield table at most
+    pub(crate) fn gas_for_resolve_claim(&self) -> Gas {
+        Gas(GAS_FOR_RESOLVE_CLAIM.0 + GAS_FOR_RESOURCE_MINT.0 * self.yields.len() as u64 + GAS_FOR_RESOLVE_MINT.0)
+    }
+
+    // Mints every resource into its resource contract, rolling the accrual back for the mints that fail
+    pub(crate) fn internal_mint(&mut self, owner_id: AccountId, character_id: Option<TokenId>, amounts: Vec<ResourceAmount>) -> Promise {
+        let mints = amounts
+            .iter()
+            .map(|amount| {
+                ext_resource::ext(amount.resource_id.clone())
+                    .with_static_gas(GAS_FOR_RESOURCE_MINT)
+                    .ft_mint(owner_id.clone(), amount.amount, None)
+            })
+            .reduce(|mints, mint| mints.and(mint))
+            .expect("Nothing to mint");
+        mints.then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(GAS_FOR_RESOLVE_MINT)
+                .resolve_mint(
+                    owner_id,
+                    character_id,
+                    amounts
+                )
+        )
+    }
+
+    /*
+        gives back the resources of a failed mint.
+        if the character is still in the location with the same owner, they are claimed again with its next claim,
+        otherwise they are kept for the owner to retry with `claim_unminted`.
+    */
+    pub(crate) fn internal_rollback_mint(&mut self, owner_id: &AccountId, character_id: &Option<TokenId>, amount: ResourceAmount) {
+        if let Some(character_id) = character_id {
+            if let Some(mut character) = self.present_characters.get(character_id) {
+                if &character.owner == owner_id {
+                    *character.unclaimed.entry(amount.resource_id).or_insert(0) += amount.amount.0;
+                    self.present_characters.insert(character_id, &character);
+                    return;
+                }
+            }
+        }
+        self.internal_add_unminted(owner_id, amount);
+    }
+
+    // Keeps resources for the owner to mint with `claim_unminted`
+    pub(crate) fn internal_add_unminted(&mut self, owner_id: &AccountId, amount: ResourceAmount) {
+        let mut unminted = self.unminted.get(owner_id).unwrap_or_default();
+        *unminted.entry(amount.resource_id).or_insert(0) += amount.amount.0;
+        self.unminted.insert(owner_id, &unminted);
+    }
+}
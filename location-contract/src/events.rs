@@ -0,0 +1,109 @@
+use std::fmt;
+
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// Enum that represents the data type of the EventLog.
+/// The enum can either be a LocationPauseUpdate or a ResourceClaim.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+#[serde(crate = "near_sdk::serde")]
+#[non_exhaustive]
+pub enum EventLogVariant {
+    LocationPauseUpdate(Vec<LocationPauseUpdateLog>),
+    ResourceClaim(Vec<ResourceClaimLog>),
+}
+
+/// Interface to capture data about an event
+///
+/// Arguments:
+/// * `standard`: name of standard e.g. pray
+/// * `version`: e.g. 1.0.0
+/// * `event`: associate event data
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EventLog {
+    pub standard: String,
+    pub version: String,
+
+    // `flatten` to not have "event": {<EventLogVariant>} in the JSON, just have the contents of {<EventLogVariant>}.
+    #[serde(flatten)]
+    pub event: EventLogVariant,
+}
+
+impl fmt::Display for EventLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!(
+            "EVENT_JSON:{}",
+            &near_sdk::serde_json::to_string(self).map_err(|_| fmt::Error)?
+        ))
+    }
+}
+
+/// An event log to capture the location being paused or resumed
+///
+/// Arguments
+/// * `paused`: whether characters can't enter and leave
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LocationPauseUpdateLog {
+    pub paused: bool,
+}
+
+/// An event log to capture resources minted to the owner of a character
+///
+/// Arguments
+/// * `owner_id`: "owner.near"
+/// * `character_id`: optional character the resources were accrued by, none when retrying with `claim_unminted`
+/// * `resource_id`: "wood.near"
+/// * `amount`: "100"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ResourceClaimLog {
+    pub owner_id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub character_id: Option<String>,
+    pub resource_id: String,
+    pub amount: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pray_format_location_pause_update() {
+        let expected = r#"EVENT_JSON:{"standard":"pray","version":"1.0.0","event":"location_pause_update","data":[{"paused":true}]}"#;
+        let log = EventLog {
+            standard: "pray".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::LocationPauseUpdate(vec![LocationPauseUpdateLog { paused: true }]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn pray_format_resource_claim() {
+        let expected = r#"EVENT_JSON:{"standard":"pray","version":"1.0.0","event":"resource_claim","data":[{"owner_id":"owner.near","character_id":"1","resource_id":"wood.near","amount":"100"},{"owner_id":"owner.near","resource_id":"stone.near","amount":"5"}]}"#;
+        let log = EventLog {
+            standard: "pray".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::ResourceClaim(vec![
+                ResourceClaimLog {
+                    owner_id: "owner.near".to_owned(),
+                    character_id: Some("1".to_owned()),
+                    resource_id: "wood.near".to_owned(),
+                    amount: "100".to_owned(),
+                },
+                ResourceClaimLog {
+                    owner_id: "owner.near".to_owned(),
+                    character_id: None,
+                    resource_id: "stone.near".to_owned(),
+                    amount: "5".to_owned(),
+                },
+            ]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+}
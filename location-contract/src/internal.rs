@@ -12,4 +12,8 @@ impl Contract {
             "owner_id should be sender_id"
         );
     }
-} 
\ No newline at end of file
+
+    pub(crate) fn assert_not_paused(&self) {
+        assert!(!self.paused, "Location is paused");
+    }
+}
\ No newline at end of file
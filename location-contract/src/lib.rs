@@ -1,45 +1,100 @@
+use std::collections::HashMap;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::{env, near_bindgen, ext_contract, AccountId, BlockHeight, PanicOnDefault, Promise, PromiseOrValue, PromiseResult, Gas};
-use near_sdk::collections::{UnorderedMap};
+use near_sdk::collections::{LookupMap, UnorderedMap};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
 pub type TokenId = String;
 
-const GAS_FOR_CHARACTER_MOVE: Gas = Gas(1_000_000_000_000);
-const GAS_FOR_RESOLVE_CHARACTER_MOVE: Gas = Gas(1_000_000_000_000);
+// Moving reads the location registry, updates where the character is and the population of the location, and logs the move
+const GAS_FOR_CHARACTER_MOVE: Gas = Gas(10_000_000_000_000);
+const GAS_FOR_CHARACTER_STATS: Gas = Gas(5_000_000_000_000);
+const GAS_FOR_CHARACTER_LOCATION: Gas = Gas(5_000_000_000_000);
+// Kept by the callback of a claim for itself, on top of the gas of the mints, see `gas_for_resolve_claim`
+const GAS_FOR_RESOLVE_CLAIM: Gas = Gas(10_000_000_000_000);
+const GAS_FOR_RESOURCE_MINT: Gas = Gas(10_000_000_000_000);
+const GAS_FOR_RESOLVE_MINT: Gas = Gas(5_000_000_000_000);
+// Moves only update the state of the location, leaving doesn't mint so that the gas doesn't grow with the yield table
+const GAS_FOR_RESOLVE_CHARACTER_MOVE: Gas = Gas(20_000_000_000_000);
 
+// Name and version of the events specific to the Pray contracts
+const PRAY_STANDARD_NAME: &str = "pray";
+const PRAY_EVENTS_VERSION: &str = "1.0.0";
+
+mod accrual;
+mod events;
 mod internal;
+mod migration;
+mod multipliers;
+
+pub use crate::accrual::*;
+pub use crate::events::*;
+pub use crate::migration::*;
+pub use crate::multipliers::*;
 
 #[near_bindgen]
 #[derive(BorshSerialize, BorshDeserialize, PanicOnDefault)]
 pub struct Contract {
     owner_id: AccountId,
     name: String, // Location name
-    rate: u32, // Base resource collection/crafting rate
+    yields: Vec<ResourceYield>, // Resources produced by the location, each at its own rate
     present_characters: UnorderedMap<TokenId, CharacterData>,
+    paused: bool, // Whether characters can enter and leave
+    character_contract_id: AccountId, // Character collection the location moves characters of
+    yield_updated_at_block: BlockHeight, // Block up to which the yields are accrued
+    unminted: LookupMap<AccountId, HashMap<AccountId, u128>>, // Resources of the characters that left, minted with `claim_unminted`
+    multipliers: Vec<YieldMultiplier>, // Scale the yield of the characters by their traits
 }
 
 #[derive(BorshSerialize)]
 pub enum StorageKey {
     PresentCharacters,
+    Unminted,
 }
 
 #[derive(BorshSerialize, BorshDeserialize)]
 pub struct CharacterData {
     pub owner: AccountId,
     pub entered_at_block: BlockHeight,
+    pub yield_checkpoints: HashMap<AccountId, u128>, // Accrual of each resource when the character entered or last claimed
+    pub multiplier_bps: u32, // Multiplier of the traits of the character when it entered
+    pub unclaimed: HashMap<AccountId, u128>, // Resources of failed mints, claimed again with the next claim
 }
 
 #[ext_contract(ext_character)]
 pub trait ExtCharacter {
     fn move_character(character_id: &TokenId, destination: Option<String>) -> Promise;
+
+    fn character_stats(token_id: TokenId) -> Option<CharacterStats>;
+
+    fn character_location(token_id: TokenId) -> Option<AccountId>;
+}
+
+#[ext_contract(ext_resource)]
+pub trait ExtResource {
+    fn ft_mint(account_id: AccountId, amount: U128, memo: Option<String>);
 }
 
 #[ext_contract(ext_self)]
-trait LocationResolver {
+pub trait LocationResolver {
     fn resolve_character_move(
         &mut self,
         character_id: &TokenId,
         enter: bool
     ) -> bool;
+
+    fn resolve_claim(
+        &mut self,
+        owner_id: AccountId,
+        character_id: TokenId
+    ) -> PromiseOrValue<Vec<ResourceAmount>>;
+
+    fn resolve_mint(
+        &mut self,
+        owner_id: AccountId,
+        character_id: Option<TokenId>,
+        amounts: Vec<ResourceAmount>
+    ) -> Vec<ResourceAmount>;
 }
 
 #[near_bindgen]
@@ -48,28 +103,66 @@ impl Contract {
     pub fn new(
         owner_id: AccountId,
         name: String,
-        rate: u32
+        yields: Vec<ResourceRate>,
+        character_contract_id: AccountId
     ) -> Self {
-        Self {
+        write_state_version();
+        let mut this = Self {
             owner_id,
             name,
-            rate,
-            present_characters: UnorderedMap::new(StorageKey::PresentCharacters.try_to_vec().unwrap())
+            yields: Vec::new(),
+            present_characters: UnorderedMap::new(StorageKey::PresentCharacters.try_to_vec().unwrap()),
+            paused: false,
+            character_contract_id,
+            yield_updated_at_block: env::block_height(),
+            unminted: LookupMap::new(StorageKey::Unminted.try_to_vec().unwrap()),
+            multipliers: Vec::new(),
+        };
+        for resource in yields {
+            this.internal_set_yield(resource.resource_id, resource.rate);
         }
+        this
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.assert_called_by_owner();
+        self.paused = paused;
+
+        let pause_log: EventLog = EventLog {
+            standard: PRAY_STANDARD_NAME.to_string(),
+            version: PRAY_EVENTS_VERSION.to_string(),
+            event: EventLogVariant::LocationPauseUpdate(vec![LocationPauseUpdateLog { paused }]),
+        };
+        env::log_str(&pause_log.to_string());
     }
 
-    pub fn set_rate(&mut self, rate: u32) {
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_character_contract_id(&mut self, character_contract_id: AccountId) {
         self.assert_called_by_owner();
-        self.rate = rate;
+        self.character_contract_id = character_contract_id;
+    }
+
+    pub fn character_contract_id(&self) -> AccountId {
+        self.character_contract_id.clone()
     }
 
     pub fn enter(&mut self, character_id: TokenId) -> PromiseOrValue<bool> {
-        ext_character::ext(AccountId::try_from("collection.pray.devgenerate.testnet".to_string()).unwrap())
+        self.assert_not_paused();
+        ext_character::ext(self.character_contract_id.clone())
             .with_static_gas(GAS_FOR_CHARACTER_MOVE)
             .move_character(
                 &character_id,
                 Some(self.name.clone())
             )
+        // The stats of the character set its multiplier
+        .and(
+            ext_character::ext(self.character_contract_id.clone())
+                .with_static_gas(GAS_FOR_CHARACTER_STATS)
+                .character_stats(character_id.clone())
+        )
         .then(
             Self::ext(env::current_account_id())
                 .with_static_gas(GAS_FOR_RESOLVE_CHARACTER_MOVE)
@@ -81,7 +174,8 @@ impl Contract {
     }
 
     pub fn leave(&mut self, character_id: TokenId) -> PromiseOrValue<bool> {
-        ext_character::ext(AccountId::try_from("collection.pray.devgenerate.testnet".to_string()).unwrap())
+        self.assert_not_paused();
+        ext_character::ext(self.character_contract_id.clone())
             .with_static_gas(GAS_FOR_CHARACTER_MOVE)
             .move_character(
                 &character_id,
@@ -110,15 +204,22 @@ impl LocationResolver for Contract {
             if let Ok(moved) = near_sdk::serde_json::from_slice::<bool>(&value) {
                 if moved {
                     if enter {
+                        // A character whose stats couldn't be fetched yields at the base multiplier
+                        let character_stats = match env::promise_result(1) {
+                            PromiseResult::Successful(value) => near_sdk::serde_json::from_slice(&value).unwrap_or(None),
+                            _ => None,
+                        };
+                        self.internal_update_yield();
                         self.present_characters.insert(&character_id, &CharacterData {
                             owner: env::signer_account_id(),
                             entered_at_block: env::block_height(),
+                            yield_checkpoints: self.current_yield_per_character(),
+                            multiplier_bps: self.internal_multiplier_bps(&character_stats),
+                            unclaimed: HashMap::new(),
                         });
                     } else {
-                        return match self.present_characters.remove(&character_id) {
-                            Some(_) => true,
-                            None => false
-                        };
+                        // The resources the character accrued are kept for its owner to mint with `claim_unminted`
+                        return self.internal_remove_character(character_id);
                     }
                     return true;
                 }
@@ -126,4 +227,68 @@ impl LocationResolver for Contract {
         }
         false
     }
-}
\ No newline at end of file
+
+    #[private]
+    fn resolve_claim(
+        &mut self,
+        owner_id: AccountId,
+        character_id: TokenId
+    ) -> PromiseOrValue<Vec<ResourceAmount>> {
+        let location = match env::promise_result(0) {
+            PromiseResult::Successful(value) => near_sdk::serde_json::from_slice::<Option<AccountId>>(&value).ok(),
+            _ => None,
+        };
+        let location = location.expect("Failed to fetch the location of the character");
+        let character = self.present_characters.get(&character_id).expect("Character is not in this location");
+        assert_eq!(character.owner, owner_id, "Only the owner of the character can claim");
+
+        // A character that left without the location knowing can't claim anymore, and since when it left is unknown what it accrued is dropped
+        if location != Some(env::current_account_id()) {
+            self.present_characters.remove(&character_id);
+            env::log_str(&format!("Character {} is no longer in {} and was removed", character_id, self.name));
+            return PromiseOrValue::Value(Vec::new());
+        }
+
+        let amounts = self.internal_settle(&character_id, character);
+        if amounts.is_empty() {
+            return PromiseOrValue::Value(amounts);
+        }
+        self.internal_mint(owner_id, Some(character_id), amounts).into()
+    }
+
+    #[private]
+    fn resolve_mint(
+        &mut self,
+        owner_id: AccountId,
+        character_id: Option<TokenId>,
+        amounts: Vec<ResourceAmount>
+    ) -> Vec<ResourceAmount> {
+        // Every resource is minted by its own promise, in the order of the amounts
+        let mut minted = Vec::new();
+        for (index, amount) in amounts.into_iter().enumerate() {
+            if let PromiseResult::Successful(_) = env::promise_result(index as u64) {
+                minted.push(amount);
+            } else {
+                self.internal_rollback_mint(&owner_id, &character_id, amount);
+            }
+        }
+
+        if !minted.is_empty() {
+            let resource_claim_log: EventLog = EventLog {
+                standard: PRAY_STANDARD_NAME.to_string(),
+                version: PRAY_EVENTS_VERSION.to_string(),
+                event: EventLogVariant::ResourceClaim(minted.iter().map(|amount| ResourceClaimLog {
+                    owner_id: owner_id.to_string(),
+                    character_id: character_id.clone(),
+                    resource_id: amount.resource_id.to_string(),
+                    amount: amount.amount.0.to_string(),
+                }).collect()),
+            };
+            env::log_str(&resource_claim_log.to_string());
+        }
+        minted
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,121 @@
+use crate::*;
+
+/*
+    version of the state layout written by this code.
+    version 2 is the final layout of the changes made since version 1, from resources and pausing to yield multipliers.
+    they were deployed together, so the intermediate layouts have no version, and the `fixtures` of the tests freeze both versions.
+    from now on, whenever the layout of `Contract` changes, bump it, freeze the previous layout below, add an arm to `migrate`
+    and a fixture of the new layout.
+*/
+pub const STATE_VERSION: u32 = 2;
+// The version is kept under its own key, so that it can be read before the state is deserialized
+const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+/*
+    every present character is rewritten by the migration from version 1, within the gas of a single call.
+    with more characters than this, some of them have to leave with the previous code before it is migrated.
+*/
+pub const MAX_MIGRATED_CHARACTERS: u64 = 100;
+
+// Layout of the state before it was versioned
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ContractV1 {
+    pub owner_id: AccountId,
+    pub name: String,
+    pub rate: u32,
+    pub present_characters: UnorderedMap<TokenId, CharacterDataV1>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct CharacterDataV1 {
+    pub owner: AccountId,
+    pub entered_at_block: BlockHeight,
+}
+
+// State written before it was versioned has no version
+pub(crate) fn read_state_version() -> u32 {
+    env::storage_read(STATE_VERSION_KEY)
+        .map(|version| u32::try_from_slice(&version).expect("Invalid state version"))
+        .unwrap_or(1)
+}
+
+pub(crate) fn write_state_version() {
+    env::storage_write(STATE_VERSION_KEY, &STATE_VERSION.try_to_vec().unwrap());
+}
+
+#[near_bindgen]
+impl Contract {
+    /*
+        upgrades the state written by a previous version of the contract, call it right after the new code is deployed.
+        version 1 produced a single resource and moved the characters of a single collection, which have to be given.
+        the characters present in it are given as well, as they are to the migration of the character contract,
+        which didn't know where they were, and have to match the ones in the state.
+    */
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate(
+        resource_id: Option<AccountId>,
+        character_contract_id: Option<AccountId>,
+        present_characters: Option<Vec<TokenId>>,
+    ) -> Self {
+        let contract = match read_state_version() {
+            1 => {
+                let resource_id = resource_id.expect("The resource produced by the location is required to migrate from version 1");
+                let character_contract_id = character_contract_id
+                    .expect("The character contract is required to migrate from version 1");
+                let mut old: ContractV1 = env::state_read().expect("No state to migrate");
+                assert!(
+                    old.present_characters.len() <= MAX_MIGRATED_CHARACTERS,
+                    "At most {} present characters can be migrated, make the others leave before migrating",
+                    MAX_MIGRATED_CHARACTERS
+                );
+                let mut given_characters = present_characters
+                    .expect("The present characters are required to migrate from version 1");
+                given_characters.sort();
+                given_characters.dedup();
+                assert!(
+                    given_characters.len() as u64 == old.present_characters.len()
+                        && given_characters.iter().all(|character_id| old.present_characters.get(character_id).is_some()),
+                    "The present characters don't match the characters in the location"
+                );
+                let characters = old.present_characters.to_vec();
+                old.present_characters.clear();
+
+                // Nothing accrued before, the characters that are present start accruing now
+                let mut present_characters = UnorderedMap::new(StorageKey::PresentCharacters.try_to_vec().unwrap());
+                for (character_id, character) in characters {
+                    present_characters.insert(&character_id, &CharacterData {
+                        owner: character.owner,
+                        entered_at_block: character.entered_at_block,
+                        yield_checkpoints: HashMap::new(),
+                        multiplier_bps: BASE_MULTIPLIER_BPS,
+                        unclaimed: HashMap::new(),
+                    });
+                }
+                Self {
+                    owner_id: old.owner_id,
+                    name: old.name,
+                    // The single rate is produced into the given resource contract
+                    yields: vec![ResourceYield {
+                        resource_id,
+                        rate: old.rate,
+                        per_character: 0,
+                    }],
+                    present_characters,
+                    paused: false,
+                    character_contract_id,
+                    yield_updated_at_block: env::block_height(),
+                    unminted: LookupMap::new(StorageKey::Unminted.try_to_vec().unwrap()),
+                    multipliers: Vec::new(),
+                }
+            }
+            version => panic!("State version {} can't be migrated to version {}", version, STATE_VERSION),
+        };
+        write_state_version();
+
+        contract
+    }
+
+    pub fn state_version(&self) -> u32 {
+        read_state_version()
+    }
+}
@@ -0,0 +1,68 @@
+use crate::*;
+
+// Multiplier of a character without matching traits, 10000 basis points is 1x
+pub const BASE_MULTIPLIER_BPS: u32 = 10_000;
+
+// Stats of a character as returned by `character_stats` on the character contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CharacterStats {
+    pub class: Option<String>,
+    pub stats: HashMap<String, u32>,
+}
+
+/*
+    scales the yield of the characters by their traits, in basis points.
+    the multiplier of a character is the one of its class, or the base multiplier if no class matches,
+    plus the bonus of every stat for each point the character has of it.
+*/
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "kind")]
+pub enum YieldMultiplier {
+    Class { class: String, multiplier_bps: u32 },
+    Stat { stat: String, bps_per_point: u32 },
+}
+
+#[near_bindgen]
+impl Contract {
+    // Applies to the characters that enter from now on, the ones present keep the multiplier they entered with
+    pub fn set_multipliers(&mut self, multipliers: Vec<YieldMultiplier>) {
+        self.assert_called_by_owner();
+        self.multipliers = multipliers;
+    }
+
+    pub fn multipliers(&self) -> Vec<YieldMultiplier> {
+        self.multipliers.clone()
+    }
+
+    // Multiplier the character entered with, None if it isn't in the location
+    pub fn character_multiplier(&self, character_id: TokenId) -> Option<u32> {
+        self.present_characters.get(&character_id).map(|character| character.multiplier_bps)
+    }
+}
+
+impl Contract {
+    // Unrevealed characters have no stats and yield at the base multiplier
+    pub(crate) fn internal_multiplier_bps(&self, character_stats: &Option<CharacterStats>) -> u32 {
+        let character_stats = match character_stats {
+            Some(character_stats) => character_stats,
+            None => return BASE_MULTIPLIER_BPS,
+        };
+
+        let mut multiplier_bps = self.multipliers
+            .iter()
+            .find_map(|multiplier| match multiplier {
+                YieldMultiplier::Class { class, multiplier_bps } if character_stats.class.as_ref() == Some(class) => Some(*multiplier_bps),
+                _ => None,
+            })
+            .unwrap_or(BASE_MULTIPLIER_BPS);
+        for multiplier in &self.multipliers {
+            if let YieldMultiplier::Stat { stat, bps_per_point } = multiplier {
+                let points = character_stats.stats.get(stat).copied().unwrap_or(0);
+                multiplier_bps = multiplier_bps.saturating_add(points.saturating_mul(*bps_per_point));
+            }
+        }
+        multiplier_bps
+    }
+}
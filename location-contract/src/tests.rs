@@ -0,0 +1,515 @@
+/* unit tests */
+use crate::{CharacterDataV1, Contract, ContractV1, LocationResolver, StorageKey, MAX_MIGRATED_CHARACTERS, STATE_VERSION};
+use crate::{ResourceAmount, ResourceRate, YieldMultiplier, BASE_MULTIPLIER_BPS, GAS_FOR_RESOLVE_CHARACTER_MOVE, GAS_FOR_RESOLVE_CLAIM};
+use near_sdk::borsh::BorshSerialize;
+use near_sdk::collections::UnorderedMap;
+use near_sdk::json_types::{Base64VecU8, U128};
+use near_sdk::test_utils::{accounts, VMContextBuilder};
+use near_sdk::{env, testing_env, AccountId, PromiseOrValue, PromiseResult, RuntimeFeesConfig, VMConfig};
+
+// Writes the state of a location with the given number of present characters before it was versioned
+fn write_v1_state(present_count: u64) {
+    testing_env!(VMContextBuilder::new().build());
+    let mut present_characters = UnorderedMap::new(StorageKey::PresentCharacters.try_to_vec().unwrap());
+    for index in 0..present_count {
+        present_characters.insert(&index.to_string(), &CharacterDataV1 { owner: accounts(1), entered_at_block: 10 });
+    }
+    env::state_write(&ContractV1 {
+        owner_id: accounts(0),
+        name: "Abandoned Ruins".to_string(),
+        rate: 2,
+        present_characters,
+    });
+}
+
+// Ids of the characters written by `write_v1_state`
+fn character_ids(present_count: u64) -> Vec<String> {
+    (0..present_count).map(|index| index.to_string()).collect()
+}
+
+/*
+    Writes a frozen storage snapshot, as pairs of base64 keys and values.
+    `state_v1.json` was written by the location before its state was versioned and `state_v2.json` by the current layout.
+*/
+fn load_state_fixture(fixture: &str) {
+    testing_env!(VMContextBuilder::new().build());
+    let entries: Vec<(Base64VecU8, Base64VecU8)> = near_sdk::serde_json::from_str(fixture).unwrap();
+    for (key, value) in entries {
+        env::storage_write(&key.0, &value.0);
+    }
+}
+
+#[test]
+fn test_migrate_from_v1() {
+    load_state_fixture(include_str!("fixtures/state_v1.json"));
+
+    let contract = Contract::migrate(Some(wood()), Some(accounts(3)), Some(vec!["0".to_string()]));
+    assert_eq!(contract.state_version(), STATE_VERSION);
+    assert_eq!(contract.name, "Abandoned Ruins");
+    assert_eq!(contract.yields(), vec![ResourceRate { resource_id: wood(), rate: 2 }]);
+    assert!(!contract.is_paused());
+    assert_eq!(contract.character_contract_id(), accounts(3));
+    let character = contract.present_characters.get(&"0".to_string()).unwrap();
+    assert_eq!(character.owner, accounts(1));
+    assert_eq!(character.entered_at_block, 10);
+    assert!(character.yield_checkpoints.is_empty());
+    assert_eq!(character.multiplier_bps, BASE_MULTIPLIER_BPS);
+}
+
+// A change of the layout breaks this test, and needs a new version with its own fixture and migration
+#[test]
+fn test_state_v2_fixture() {
+    load_state_fixture(include_str!("fixtures/state_v2.json"));
+
+    let contract: Contract = env::state_read().unwrap();
+    assert_eq!(contract.state_version(), STATE_VERSION);
+    assert_eq!(contract.yields(), vec![ResourceRate { resource_id: wood(), rate: 2 }, ResourceRate { resource_id: stone(), rate: 1 }]);
+    assert_eq!(contract.character_contract_id(), accounts(3));
+    assert_eq!(contract.character_multiplier("0".to_string()), Some(16_000));
+    assert_eq!(contract.character_multiplier("1".to_string()), None);
+    assert_eq!(contract.multipliers().len(), 3);
+    assert_eq!(contract.unminted(accounts(1)), vec![amount(stone(), 8), amount(wood(), 16)]);
+}
+
+#[test]
+#[should_panic(expected = "State version 2 can't be migrated to version 2")]
+fn test_migrate_current_state() {
+    testing_env!(VMContextBuilder::new().build());
+    let contract = Contract::new(accounts(0), "Abandoned Ruins".to_string(), vec![], accounts(1));
+    env::state_write(&contract);
+    Contract::migrate(None, None, None);
+}
+
+#[test]
+#[should_panic(expected = "The resource produced by the location is required to migrate from version 1")]
+fn test_migrate_from_v1_no_resource() {
+    write_v1_state(1);
+    Contract::migrate(None, Some(accounts(3)), Some(vec!["0".to_string()]));
+}
+
+#[test]
+fn test_migrate_from_v1_max_characters() {
+    write_v1_state(MAX_MIGRATED_CHARACTERS);
+    let contract = Contract::migrate(Some(wood()), Some(accounts(3)), Some(character_ids(MAX_MIGRATED_CHARACTERS)));
+    assert_eq!(contract.present_characters.len(), MAX_MIGRATED_CHARACTERS);
+    //the storage operations leave half of the gas of a call for the execution of the code
+    assert!(env::used_gas() < near_sdk::Gas(150_000_000_000_000));
+}
+
+#[test]
+#[should_panic(expected = "At most 100 present characters can be migrated")]
+fn test_migrate_from_v1_too_many_characters() {
+    write_v1_state(MAX_MIGRATED_CHARACTERS + 1);
+    Contract::migrate(Some(wood()), Some(accounts(3)), Some(character_ids(MAX_MIGRATED_CHARACTERS + 1)));
+}
+
+// The characters given to the migrations of both contracts are the ones present in the location
+#[test]
+#[should_panic(expected = "The present characters don't match the characters in the location")]
+fn test_migrate_from_v1_missing_character() {
+    write_v1_state(2);
+    Contract::migrate(Some(wood()), Some(accounts(3)), Some(vec!["0".to_string(), "0".to_string()]));
+}
+
+#[test]
+#[should_panic(expected = "The present characters don't match the characters in the location")]
+fn test_migrate_from_v1_absent_character() {
+    write_v1_state(1);
+    Contract::migrate(Some(wood()), Some(accounts(3)), Some(vec!["1".to_string()]));
+}
+
+#[test]
+#[should_panic(expected = "The present characters are required to migrate from version 1")]
+fn test_migrate_from_v1_no_present_characters() {
+    write_v1_state(1);
+    Contract::migrate(Some(wood()), Some(accounts(3)), None);
+}
+
+fn get_context(block_index: u64) -> VMContextBuilder {
+    let mut builder = VMContextBuilder::new();
+    builder
+        .current_account_id(accounts(2))
+        .predecessor_account_id(accounts(0))
+        .signer_account_id(accounts(0))
+        .block_index(block_index);
+    builder
+}
+
+// Resolves a successful move of the character by the character contract, signed by its owner
+fn resolve_move(contract: &mut Contract, character_id: &str, enter: bool, block_index: u64) -> bool {
+    resolve_move_with_stats(contract, character_id, enter, block_index, "null")
+}
+
+// Same as `resolve_move`, where entering also fetched the stats of the character
+fn resolve_move_with_stats(contract: &mut Contract, character_id: &str, enter: bool, block_index: u64, character_stats: &str) -> bool {
+    let mut results = vec![PromiseResult::Successful(b"true".to_vec())];
+    if enter {
+        results.push(PromiseResult::Successful(character_stats.as_bytes().to_vec()));
+    }
+    testing_env!(
+        get_context(block_index).predecessor_account_id(accounts(2)).signer_account_id(accounts(1)).build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        Default::default(),
+        results
+    );
+    contract.resolve_character_move(&character_id.to_string(), enter)
+}
+
+fn wood() -> AccountId {
+    "wood.testnet".parse().unwrap()
+}
+
+fn stone() -> AccountId {
+    "stone.testnet".parse().unwrap()
+}
+
+fn amount(resource_id: AccountId, amount: u128) -> ResourceAmount {
+    ResourceAmount { resource_id, amount: U128(amount) }
+}
+
+// Claims the accrual of the character as its owner, once the character contract answered where the character is
+fn claim(contract: &mut Contract, character_id: &str, character_location: Option<AccountId>, block_index: u64) -> PromiseOrValue<Vec<ResourceAmount>> {
+    testing_env!(get_context(block_index).predecessor_account_id(accounts(1)).build());
+    assert!(matches!(contract.claim(character_id.to_string()), PromiseOrValue::Promise(_)));
+
+    testing_env!(
+        get_context(block_index).predecessor_account_id(accounts(2)).build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        Default::default(),
+        vec![PromiseResult::Successful(near_sdk::serde_json::to_vec(&character_location).unwrap())]
+    );
+    contract.resolve_claim(accounts(1), character_id.to_string())
+}
+
+// Resolves the mints of the resources accrued by the character, each of which succeeded or failed
+fn resolve_mint(contract: &mut Contract, character_id: Option<&str>, amounts: Vec<(ResourceAmount, bool)>, block_index: u64) -> Vec<ResourceAmount> {
+    let results = amounts
+        .iter()
+        .map(|(_, minted)| if *minted { PromiseResult::Successful(vec![]) } else { PromiseResult::Failed })
+        .collect();
+    testing_env!(
+        get_context(block_index).predecessor_account_id(accounts(2)).build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        Default::default(),
+        results
+    );
+    contract.resolve_mint(
+        accounts(1),
+        character_id.map(|character_id| character_id.to_string()),
+        amounts.into_iter().map(|(amount, _)| amount).collect(),
+    )
+}
+
+// Location producing 2 wood and 1 stone per block
+fn get_contract() -> Contract {
+    testing_env!(get_context(10).build());
+    let yields = vec![ResourceRate { resource_id: wood(), rate: 2 }, ResourceRate { resource_id: stone(), rate: 1 }];
+    Contract::new(accounts(0), "Abandoned Ruins".to_string(), yields, accounts(3))
+}
+
+#[test]
+fn test_accrual() {
+    let mut contract = get_contract();
+    assert!(resolve_move(&mut contract, "0", true, 10));
+
+    testing_env!(get_context(15).build());
+    assert_eq!(contract.pending_yield("0".to_string()), Some(vec![amount(wood(), 10), amount(stone(), 5)]));
+    contract.set_yield(wood(), 5);
+
+    testing_env!(get_context(20).build());
+    assert_eq!(contract.pending_yield("0".to_string()), Some(vec![amount(wood(), 35), amount(stone(), 10)]));
+    assert!(matches!(claim(&mut contract, "0", Some(accounts(2)), 20), PromiseOrValue::Promise(_)));
+    assert_eq!(contract.pending_yield("0".to_string()), Some(vec![amount(wood(), 0), amount(stone(), 0)]));
+
+    let minted = resolve_mint(&mut contract, Some("0"), vec![(amount(wood(), 35), true), (amount(stone(), 10), true)], 20);
+    assert_eq!(minted, vec![amount(wood(), 35), amount(stone(), 10)]);
+    assert_eq!(
+        near_sdk::test_utils::get_logs(),
+        vec![r#"EVENT_JSON:{"standard":"pray","version":"1.0.0","event":"resource_claim","data":[{"owner_id":"bob","character_id":"0","resource_id":"wood.testnet","amount":"35"},{"owner_id":"bob","character_id":"0","resource_id":"stone.testnet","amount":"10"}]}"#]
+    );
+}
+
+#[test]
+fn test_accrual_per_character() {
+    let mut contract = get_contract();
+    assert!(resolve_move(&mut contract, "0", true, 10));
+    assert!(resolve_move(&mut contract, "1", true, 14));
+
+    testing_env!(get_context(20).build());
+    assert_eq!(contract.pending_yield("0".to_string()), Some(vec![amount(wood(), 20), amount(stone(), 10)]));
+    assert_eq!(contract.pending_yield("1".to_string()), Some(vec![amount(wood(), 12), amount(stone(), 6)]));
+    assert_eq!(contract.pending_yield("2".to_string()), None);
+}
+
+#[test]
+fn test_set_yield() {
+    let mut contract = get_contract();
+    assert!(resolve_move(&mut contract, "0", true, 10));
+
+    //a resource added while the character is present accrues from then on
+    testing_env!(get_context(14).build());
+    let iron: AccountId = "iron.testnet".parse().unwrap();
+    contract.set_yield(iron.clone(), 3);
+    contract.set_yield(stone(), 0);
+    assert_eq!(contract.yields(), vec![
+        ResourceRate { resource_id: wood(), rate: 2 },
+        ResourceRate { resource_id: stone(), rate: 0 },
+        ResourceRate { resource_id: iron.clone(), rate: 3 },
+    ]);
+
+    testing_env!(get_context(20).build());
+    assert_eq!(
+        contract.pending_yield("0".to_string()),
+        Some(vec![amount(wood(), 20), amount(stone(), 4), amount(iron, 18)])
+    );
+}
+
+#[test]
+#[should_panic(expected = "owner_id should be sender_id")]
+fn test_set_yield_not_owner() {
+    let mut contract = get_contract();
+
+    testing_env!(get_context(20).predecessor_account_id(accounts(1)).build());
+    contract.set_yield(wood(), 5);
+}
+
+#[test]
+fn test_claim_nothing() {
+    let mut contract = get_contract();
+    assert!(resolve_move(&mut contract, "0", true, 10));
+
+    assert!(matches!(claim(&mut contract, "0", Some(accounts(2)), 10), PromiseOrValue::Value(amounts) if amounts.is_empty()));
+}
+
+#[test]
+fn test_claim_mint_failed() {
+    let mut contract = get_contract();
+    assert!(resolve_move(&mut contract, "0", true, 10));
+
+    claim(&mut contract, "0", Some(accounts(2)), 20);
+
+    //the resource that failed is kept on the character, so it is claimed again with the next claim
+    let minted = resolve_mint(&mut contract, Some("0"), vec![(amount(wood(), 20), false), (amount(stone(), 10), true)], 21);
+    assert_eq!(minted, vec![amount(stone(), 10)]);
+    assert_eq!(contract.pending_yield("0".to_string()), Some(vec![amount(wood(), 22), amount(stone(), 1)]));
+    assert!(contract.unminted(accounts(1)).is_empty());
+}
+
+#[test]
+fn test_leave() {
+    let mut contract = get_contract();
+    assert!(resolve_move(&mut contract, "0", true, 10));
+    assert!(resolve_move(&mut contract, "0", false, 20));
+    assert_eq!(contract.pending_yield("0".to_string()), None);
+
+    //leaving doesn't mint, the resources are kept for the owner to claim
+    assert!(near_sdk::test_utils::get_created_receipts().is_empty());
+    assert_eq!(contract.unminted(accounts(1)), vec![amount(stone(), 10), amount(wood(), 20)]);
+
+    testing_env!(get_context(22).predecessor_account_id(accounts(1)).build());
+    assert!(matches!(contract.claim_unminted(), PromiseOrValue::Promise(_)));
+    assert!(contract.unminted(accounts(1)).is_empty());
+}
+
+#[test]
+fn test_leave_mint_failed() {
+    let mut contract = get_contract();
+    assert!(resolve_move(&mut contract, "0", true, 10));
+    assert!(resolve_move(&mut contract, "0", false, 20));
+
+    testing_env!(get_context(21).predecessor_account_id(accounts(1)).build());
+    contract.claim_unminted();
+
+    //the mints of a character that left are kept for the owner to retry
+    resolve_mint(&mut contract, None, vec![(amount(stone(), 10), true), (amount(wood(), 20), false)], 21);
+    assert_eq!(contract.unminted(accounts(1)), vec![amount(wood(), 20)]);
+}
+
+#[test]
+fn test_leave_gas() {
+    let mut contract = get_contract();
+    for index in 0..20 {
+        contract.set_yield(format!("resource{}.testnet", index).parse().unwrap(), 1);
+    }
+    assert!(resolve_move(&mut contract, "0", true, 10));
+
+    //the callback of a leave fits in the gas attached to it whatever the size of the yield table
+    testing_env!(
+        get_context(20).predecessor_account_id(accounts(2)).signer_account_id(accounts(1)).prepaid_gas(GAS_FOR_RESOLVE_CHARACTER_MOVE).build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        Default::default(),
+        vec![PromiseResult::Successful(b"true".to_vec())]
+    );
+    assert!(contract.resolve_character_move(&"0".to_string(), false));
+    assert!(env::used_gas() < GAS_FOR_RESOLVE_CHARACTER_MOVE);
+    assert_eq!(contract.unminted(accounts(1)).len(), 22);
+}
+
+#[test]
+fn test_claim_character_left() {
+    let mut contract = get_contract();
+    assert!(resolve_move(&mut contract, "0", true, 10));
+
+    //the character was transferred without the location being told, so its old owner can't claim with it
+    assert!(matches!(claim(&mut contract, "0", None, 20), PromiseOrValue::Value(amounts) if amounts.is_empty()));
+    assert_eq!(contract.pending_yield("0".to_string()), None);
+    assert!(contract.unminted(accounts(1)).is_empty());
+    assert_eq!(near_sdk::test_utils::get_logs(), vec!["Character 0 is no longer in Abandoned Ruins and was removed"]);
+}
+
+#[test]
+fn test_claim_gas() {
+    let mut contract = get_contract();
+    assert!(resolve_move(&mut contract, "0", true, 10));
+
+    testing_env!(get_context(20).predecessor_account_id(accounts(1)).build());
+    contract.claim("0".to_string());
+
+    //the callback of a claim attaches the gas of its mints on top of the gas it keeps for itself
+    testing_env!(
+        get_context(20).predecessor_account_id(accounts(2)).build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        Default::default(),
+        vec![PromiseResult::Successful(b"\"charlie\"".to_vec())]
+    );
+    assert!(matches!(contract.resolve_claim(accounts(1), "0".to_string()), PromiseOrValue::Promise(_)));
+    let receipts = near_sdk::test_utils::get_created_receipts();
+    assert_eq!(receipts.len(), 3);
+    let attached_gas: u64 = receipts
+        .iter()
+        .flat_map(|receipt| receipt.actions.iter())
+        .map(|action| match action {
+            near_sdk::mock::VmAction::FunctionCall { gas, .. } => gas.0,
+            _ => 0,
+        })
+        .sum();
+    assert_eq!(attached_gas + GAS_FOR_RESOLVE_CLAIM.0, contract.gas_for_resolve_claim().0);
+}
+
+#[test]
+fn test_on_force_leave() {
+    let mut contract = get_contract();
+    assert!(resolve_move(&mut contract, "0", true, 10));
+
+    //the owner of the character when it was forced to leave keeps what it accrued
+    testing_env!(get_context(20).predecessor_account_id(accounts(3)).build());
+    assert!(contract.on_force_leave("0".to_string()));
+    assert!(!contract.on_force_leave("0".to_string()));
+    assert_eq!(contract.pending_yield("0".to_string()), None);
+    assert_eq!(contract.unminted(accounts(1)), vec![amount(stone(), 10), amount(wood(), 20)]);
+}
+
+#[test]
+#[should_panic(expected = "Can only be called by the character contract")]
+fn test_on_force_leave_not_character_contract() {
+    let mut contract = get_contract();
+    assert!(resolve_move(&mut contract, "0", true, 10));
+
+    testing_env!(get_context(20).predecessor_account_id(accounts(1)).build());
+    contract.on_force_leave("0".to_string());
+}
+
+#[test]
+fn test_evict() {
+    let mut contract = get_contract();
+    assert!(resolve_move(&mut contract, "0", true, 10));
+
+    testing_env!(get_context(20).build());
+    assert!(contract.evict("0".to_string()));
+    assert_eq!(contract.pending_yield("0".to_string()), None);
+    assert_eq!(contract.unminted(accounts(1)), vec![amount(stone(), 10), amount(wood(), 20)]);
+}
+
+#[test]
+#[should_panic(expected = "owner_id should be sender_id")]
+fn test_evict_not_owner() {
+    let mut contract = get_contract();
+    assert!(resolve_move(&mut contract, "0", true, 10));
+
+    testing_env!(get_context(20).predecessor_account_id(accounts(1)).build());
+    contract.evict("0".to_string());
+}
+
+#[test]
+#[should_panic(expected = "Only the owner of the character can claim")]
+fn test_claim_not_owner() {
+    let mut contract = get_contract();
+    assert!(resolve_move(&mut contract, "0", true, 10));
+
+    testing_env!(get_context(20).predecessor_account_id(accounts(4)).build());
+    contract.claim("0".to_string());
+}
+
+// Villagers yield 1.5x, plus 1% per point of strength
+fn get_contract_with_multipliers() -> Contract {
+    let mut contract = get_contract();
+    contract.set_multipliers(vec![
+        YieldMultiplier::Class { class: "Villager".to_string(), multiplier_bps: 15_000 },
+        YieldMultiplier::Class { class: "Priest".to_string(), multiplier_bps: 5_000 },
+        YieldMultiplier::Stat { stat: "Strength".to_string(), bps_per_point: 100 },
+    ]);
+    contract
+}
+
+#[test]
+fn test_multipliers() {
+    let mut contract = get_contract_with_multipliers();
+    assert!(resolve_move_with_stats(&mut contract, "0", true, 10, r#"{"class":"Villager","stats":{"Strength":10}}"#));
+    assert!(resolve_move_with_stats(&mut contract, "1", true, 10, r#"{"class":"Priest","stats":{}}"#));
+    assert!(resolve_move_with_stats(&mut contract, "2", true, 10, r#"{"class":"Knight","stats":{"Wisdom":7}}"#));
+    assert!(resolve_move(&mut contract, "3", true, 10));
+
+    assert_eq!(contract.character_multiplier("0".to_string()), Some(16_000));
+    assert_eq!(contract.character_multiplier("1".to_string()), Some(5_000));
+    assert_eq!(contract.character_multiplier("2".to_string()), Some(BASE_MULTIPLIER_BPS));
+    assert_eq!(contract.character_multiplier("3".to_string()), Some(BASE_MULTIPLIER_BPS));
+    assert_eq!(contract.character_multiplier("4".to_string()), None);
+
+    testing_env!(get_context(20).build());
+    assert_eq!(contract.pending_yield("0".to_string()), Some(vec![amount(wood(), 32), amount(stone(), 16)]));
+    assert_eq!(contract.pending_yield("1".to_string()), Some(vec![amount(wood(), 10), amount(stone(), 5)]));
+    assert_eq!(contract.pending_yield("2".to_string()), Some(vec![amount(wood(), 20), amount(stone(), 10)]));
+}
+
+#[test]
+fn test_multipliers_stats_failed() {
+    let mut contract = get_contract_with_multipliers();
+    testing_env!(
+        get_context(10).predecessor_account_id(accounts(2)).signer_account_id(accounts(1)).build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        Default::default(),
+        vec![PromiseResult::Successful(b"true".to_vec()), PromiseResult::Failed]
+    );
+    assert!(contract.resolve_character_move(&"0".to_string(), true));
+    assert_eq!(contract.character_multiplier("0".to_string()), Some(BASE_MULTIPLIER_BPS));
+}
+
+#[test]
+fn test_multipliers_claim_mint_failed() {
+    let mut contract = get_contract_with_multipliers();
+    assert!(resolve_move_with_stats(&mut contract, "0", true, 10, r#"{"class":"Villager","stats":{}}"#));
+
+    claim(&mut contract, "0", Some(accounts(2)), 20);
+
+    //the failed resource is given back as it was minted, without being scaled again
+    resolve_mint(&mut contract, Some("0"), vec![(amount(wood(), 30), false), (amount(stone(), 15), true)], 20);
+    assert_eq!(contract.pending_yield("0".to_string()), Some(vec![amount(wood(), 30), amount(stone(), 0)]));
+
+    testing_env!(get_context(22).predecessor_account_id(accounts(1)).build());
+    assert_eq!(contract.pending_yield("0".to_string()), Some(vec![amount(wood(), 36), amount(stone(), 3)]));
+}
+
+#[test]
+#[should_panic(expected = "owner_id should be sender_id")]
+fn test_set_multipliers_not_owner() {
+    let mut contract = get_contract();
+
+    testing_env!(get_context(20).predecessor_account_id(accounts(1)).build());
+    contract.set_multipliers(vec![]);
+}
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use near_sdk::json_types::Base64VecU8;
+use pray_character::{TokenId, TokenMetadata};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+pub fn sha256(data: &[u8]) -> Vec<u8> {
+    Sha256::digest(data).to_vec()
+}
+
+/*
+    read the output directory of the generative art script:
+    <dir>/json/<token_id> holds the token's metadata and <dir>/images/<token_id>.png its image.
+    returns the token metadata sorted by token id.
+*/
+pub fn read_collection(collection_dir: &Path, metadata_cid: &str) -> Result<Vec<(TokenId, TokenMetadata)>, String> {
+    let json_dir = collection_dir.join("json");
+    let mut token_ids: Vec<u64> = fs::read_dir(&json_dir)
+        .map_err(|e| format!("failed to read {}: {}", json_dir.display(), e))?
+        .map(|entry| {
+            let file_name = entry.map_err(|e| e.to_string())?.file_name();
+            let file_name = file_name.to_string_lossy();
+            file_name.parse().map_err(|_| format!("unexpected file {} in {}", file_name, json_dir.display()))
+        })
+        .collect::<Result<_, String>>()?;
+    token_ids.sort_unstable();
+
+    token_ids
+        .into_iter()
+        .map(|token_id| {
+            let token_id = token_id.to_string();
+            let metadata = read_token_metadata(collection_dir, metadata_cid, &token_id)?;
+            Ok((token_id, metadata))
+        })
+        .collect()
+}
+
+fn read_token_metadata(collection_dir: &Path, metadata_cid: &str, token_id: &str) -> Result<TokenMetadata, String> {
+    let json_path = collection_dir.join("json").join(token_id);
+    let media_path = collection_dir.join("images").join(format!("{}.png", token_id));
+    let contents = fs::read(&json_path).map_err(|e| format!("failed to read {}: {}", json_path.display(), e))?;
+    let media = fs::read(&media_path).map_err(|e| format!("failed to read {}: {}", media_path.display(), e))?;
+
+    let metadata: Value = serde_json::from_slice(&contents)
+        .map_err(|e| format!("failed to parse {}: {}", json_path.display(), e))?;
+    let field = |name: &str| {
+        metadata[name]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| format!("{} has no \"{}\"", json_path.display(), name))
+    };
+
+    Ok(TokenMetadata {
+        title: Some(field("name")?),
+        description: Some(field("description")?),
+        media: Some(field("image")?.replace("ipfs:/", "https://ipfs.io/ipfs")),
+        media_hash: Some(Base64VecU8(sha256(&media))),
+        copies: None,
+        issued_at: None,
+        expires_at: None,
+        starts_at: None,
+        updated_at: None,
+        extra: Some(json!({ "attributes": metadata["attributes"] }).to_string()),
+        reference: Some(format!("ipfs://{}/{}", metadata_cid, token_id)),
+        reference_hash: Some(Base64VecU8(sha256(&contents))),
+    })
+}
+
+//split the collection into the batches revealed by a single `reveal` call each
+pub fn into_batches(
+    tokens: Vec<(TokenId, TokenMetadata)>,
+    batch_size: Option<usize>,
+) -> Vec<HashMap<TokenId, TokenMetadata>> {
+    let batch_size = batch_size.unwrap_or(tokens.len()).max(1);
+    let mut batches = Vec::new();
+    let mut tokens = tokens.into_iter().peekable();
+    while tokens.peek().is_some() {
+        batches.push(tokens.by_ref().take(batch_size).collect());
+    }
+    batches
+}
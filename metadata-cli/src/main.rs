@@ -0,0 +1,126 @@
+/*
+    prepares the encrypted metadata batches of the collection, which `make add_metadata` uploads.
+    batches are encrypted with the same code the character contract decrypts them with.
+*/
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use clap::Parser;
+use pray_character::{aes_gcm_encrypt, check_kdf_rounds, derive_key, TokenId, TokenMetadata};
+use rand::RngCore;
+use serde_json::json;
+
+use crate::collection::*;
+
+mod collection;
+
+#[derive(Parser)]
+#[clap(about = "Encrypt metadata of the collection")]
+struct Args {
+    /// directory of the collection
+    #[clap(long)]
+    dir: PathBuf,
+    /// collection's metadata CID
+    #[clap(long)]
+    cid: String,
+    /// number of tokens per metadata batch
+    #[clap(long)]
+    batch_size: Option<usize>,
+    /// number of PBKDF2 rounds used to derive the key
+    #[clap(long, default_value_t = 10_000)]
+    kdf_rounds: u32,
+    /// key the metadata is encrypted with, committed to by `commit_reveal_key`
+    #[clap(long, env = "REVEAL_KEY", hide_env_values = true)]
+    password: String,
+    /// directory the batches are written to
+    #[clap(long, default_value = "out")]
+    out_dir: PathBuf,
+}
+
+//batch encrypted for the upload together with the provenance hash of its plaintext
+pub struct EncryptedBatch {
+    pub encrypted: String,
+    pub plaintext_hash: Vec<u8>,
+}
+
+//every batch of the collection is encrypted with the same salt, so that the contract derives the key once
+pub fn encrypt_batch(
+    password: &str,
+    kdf_rounds: u32,
+    salt: &[u8; 16],
+    batch: &HashMap<TokenId, TokenMetadata>,
+) -> Result<EncryptedBatch, String> {
+    let plaintext = serde_json::to_string(batch).map_err(|e| e.to_string())?;
+
+    let mut nonce = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let encrypted = aes_gcm_encrypt(password, &plaintext, kdf_rounds, salt, &nonce).map_err(|e| e.to_string())?;
+    Ok(EncryptedBatch {
+        encrypted,
+        plaintext_hash: sha256(plaintext.as_bytes()),
+    })
+}
+
+//commitment to the reveal key for `commit_reveal_key`: the hash of the key derived from it, rather than of the key itself
+pub fn reveal_key_hash(password: &str, kdf_rounds: u32, salt: &[u8; 16]) -> Result<Vec<u8>, String> {
+    derive_key(password, salt, kdf_rounds).map(|key| sha256(&key)).map_err(|e| e.to_string())
+}
+
+/*
+    write every batch into <out_dir>/<cid>/<batch_number>, the provenance commitment, the hashes of the plaintext batches
+    in the upload order, into <out_dir>/<cid>.provenance.json and the reveal key commitment into <out_dir>/<cid>.reveal_key_hash.json
+*/
+pub fn write_batches(out_dir: &Path, metadata_cid: &str, batches: &[EncryptedBatch], reveal_key_hash: &[u8]) -> Result<(), String> {
+    let batch_dir = out_dir.join(metadata_cid);
+    if batch_dir.is_dir() {
+        fs::remove_dir_all(&batch_dir).map_err(|e| e.to_string())?;
+    }
+    fs::create_dir_all(&batch_dir).map_err(|e| e.to_string())?;
+
+    for (batch_number, batch) in batches.iter().enumerate() {
+        fs::write(batch_dir.join(batch_number.to_string()), &batch.encrypted).map_err(|e| e.to_string())?;
+    }
+
+    let batch_hashes: Vec<String> = batches.iter().map(|batch| base64::encode(&batch.plaintext_hash)).collect();
+    fs::write(
+        out_dir.join(format!("{}.provenance.json", metadata_cid)),
+        json!({ "batch_hashes": batch_hashes }).to_string(),
+    )
+    .map_err(|e| e.to_string())?;
+    fs::write(
+        out_dir.join(format!("{}.reveal_key_hash.json", metadata_cid)),
+        json!({ "key_hash": base64::encode(reveal_key_hash) }).to_string(),
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn run(args: Args) -> Result<(), String> {
+    //the rounds are checked by the contract's code before anything is read
+    check_kdf_rounds(args.kdf_rounds).map_err(|e| format!("--kdf-rounds: {}", e))?;
+
+    let mut salt = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut salt);
+
+    let tokens = read_collection(&args.dir, &args.cid)?;
+    let batches = into_batches(tokens, args.batch_size)
+        .iter()
+        .map(|batch| encrypt_batch(&args.password, args.kdf_rounds, &salt, batch))
+        .collect::<Result<Vec<_>, String>>()?;
+    write_batches(&args.out_dir, &args.cid, &batches, &reveal_key_hash(&args.password, args.kdf_rounds, &salt)?)?;
+
+    println!("{} batches written to {}", batches.len(), args.out_dir.join(&args.cid).display());
+    Ok(())
+}
+
+fn main() {
+    if let Err(error) = run(Args::parse()) {
+        eprintln!("error: {}", error);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests;
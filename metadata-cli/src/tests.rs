@@ -0,0 +1,132 @@
+/* unit tests */
+use crate::*;
+use pray_character::{aes_gcm_decrypt, kdf_params, DerivedKey, KdfParams, RevealError};
+
+use std::collections::HashMap;
+use std::env;
+
+const PASSWORD: &str = "password";
+const KDF_ROUNDS: u32 = 1_000;
+const SALT: [u8; 16] = [7; 16];
+
+//writes a small generative art output into a fresh temporary directory
+fn sample_collection(name: &str, size: usize) -> PathBuf {
+    let dir = env::temp_dir().join(format!("pray-metadata-{}-{}", name, process::id()));
+    if dir.is_dir() {
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    fs::create_dir_all(dir.join("json")).unwrap();
+    fs::create_dir_all(dir.join("images")).unwrap();
+
+    for token_id in 0..size {
+        let metadata = json!({
+            "name": format!("Villager {}", token_id),
+            "description": "Villager",
+            "image": format!("ipfs://QmImages/{}.png", token_id),
+            "attributes": [{"trait_type": "Class", "value": "Villager"}],
+        });
+        fs::write(dir.join("json").join(token_id.to_string()), metadata.to_string()).unwrap();
+        fs::write(dir.join("images").join(format!("{}.png", token_id)), format!("image {}", token_id)).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn test_read_collection() {
+    let dir = sample_collection("read", 12);
+    let tokens = read_collection(&dir, "QmMetadata").unwrap();
+
+    let token_ids: Vec<&str> = tokens.iter().map(|(token_id, _)| token_id.as_str()).collect();
+    assert_eq!(token_ids, vec!["0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11"]);
+
+    let (_, metadata) = &tokens[10];
+    assert_eq!(metadata.title, Some("Villager 10".to_string()));
+    assert_eq!(metadata.media, Some("https://ipfs.io/ipfs/QmImages/10.png".to_string()));
+    assert_eq!(metadata.media_hash.as_ref().unwrap().0, sha256(b"image 10"));
+    assert_eq!(metadata.reference, Some("ipfs://QmMetadata/10".to_string()));
+    assert_eq!(
+        metadata.extra,
+        Some(r#"{"attributes":[{"trait_type":"Class","value":"Villager"}]}"#.to_string())
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_into_batches() {
+    let dir = sample_collection("batches", 5);
+    let tokens = read_collection(&dir, "QmMetadata").unwrap();
+
+    let sizes: Vec<usize> = into_batches(tokens.clone(), Some(2)).iter().map(HashMap::len).collect();
+    assert_eq!(sizes, vec![2, 2, 1]);
+    let sizes: Vec<usize> = into_batches(tokens, None).iter().map(HashMap::len).collect();
+    assert_eq!(sizes, vec![5]);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_round_trip() {
+    let dir = sample_collection("round-trip", 3);
+    let batch: HashMap<TokenId, TokenMetadata> = read_collection(&dir, "QmMetadata").unwrap().into_iter().collect();
+
+    let encrypted_batch = encrypt_batch(PASSWORD, KDF_ROUNDS, &SALT, &batch).unwrap();
+
+    //the contract decrypts the batch, matches it against the provenance and parses the metadata
+    let plaintext = aes_gcm_decrypt(PASSWORD, &encrypted_batch.encrypted, false).unwrap();
+    assert_eq!(sha256(plaintext.as_bytes()), encrypted_batch.plaintext_hash);
+    let decrypted: HashMap<TokenId, TokenMetadata> = serde_json::from_str(&plaintext).unwrap();
+    assert_eq!(decrypted.len(), 3);
+    assert_eq!(decrypted["2"].title, Some("Villager 2".to_string()));
+    assert_eq!(decrypted["2"].reference_hash, batch["2"].reference_hash);
+
+    assert_eq!(
+        aes_gcm_decrypt("wrong password", &encrypted_batch.encrypted, false),
+        Err(RevealError::Decryption)
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_write_batches() {
+    let dir = sample_collection("write", 3);
+    let out_dir = dir.join("out");
+    let batches: Vec<EncryptedBatch> = into_batches(read_collection(&dir, "QmMetadata").unwrap(), Some(2))
+        .iter()
+        .map(|batch| encrypt_batch(PASSWORD, KDF_ROUNDS, &SALT, batch).unwrap())
+        .collect();
+    write_batches(&out_dir, "QmMetadata", &batches, &reveal_key_hash(PASSWORD, KDF_ROUNDS, &SALT).unwrap()).unwrap();
+
+    let provenance: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(out_dir.join("QmMetadata.provenance.json")).unwrap()).unwrap();
+    for (batch_number, batch_hash) in provenance["batch_hashes"].as_array().unwrap().iter().enumerate() {
+        let encrypted = fs::read_to_string(out_dir.join("QmMetadata").join(batch_number.to_string())).unwrap();
+        let plaintext = aes_gcm_decrypt(PASSWORD, &encrypted, false).unwrap();
+        assert_eq!(batch_hash.as_str().unwrap(), base64::encode(sha256(plaintext.as_bytes())));
+        //the contract derives the key once for all the batches
+        assert_eq!(kdf_params(&encrypted), Ok(KdfParams { rounds: KDF_ROUNDS, salt: SALT }));
+    }
+
+    //the contract checks the reveal key against the hash of the key derived from it
+    let reveal_key_hash: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(out_dir.join("QmMetadata.reveal_key_hash.json")).unwrap()).unwrap();
+    let key = DerivedKey::derive(PASSWORD, KdfParams { rounds: KDF_ROUNDS, salt: SALT }).unwrap();
+    assert_eq!(reveal_key_hash["key_hash"].as_str().unwrap(), base64::encode(sha256(&key.key)));
+    assert_ne!(reveal_key_hash["key_hash"].as_str().unwrap(), base64::encode(sha256(PASSWORD.as_bytes())));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_run_weak_key_derivation() {
+    let args = Args {
+        dir: PathBuf::from("missing"),
+        cid: "QmMetadata".to_string(),
+        batch_size: None,
+        kdf_rounds: 10,
+        password: PASSWORD.to_string(),
+        out_dir: PathBuf::from("out"),
+    };
+    assert_eq!(run(args), Err("--kdf-rounds: key derivation uses too few rounds".to_string()));
+}
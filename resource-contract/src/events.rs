@@ -0,0 +1,143 @@
+use std::fmt;
+
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// Enum that represents the data type of the EventLog.
+/// The enum can either be an FtMint, an FtTransfer or an FtBurn.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+#[serde(crate = "near_sdk::serde")]
+#[non_exhaustive]
+pub enum EventLogVariant {
+    FtMint(Vec<FtMintLog>),
+    FtTransfer(Vec<FtTransferLog>),
+    FtBurn(Vec<FtBurnLog>),
+}
+
+/// Interface to capture data about an event
+///
+/// Arguments:
+/// * `standard`: name of standard e.g. nep141
+/// * `version`: e.g. 1.0.0
+/// * `event`: associate event data
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EventLog {
+    pub standard: String,
+    pub version: String,
+
+    // `flatten` to not have "event": {<EventLogVariant>} in the JSON, just have the contents of {<EventLogVariant>}.
+    #[serde(flatten)]
+    pub event: EventLogVariant,
+}
+
+impl fmt::Display for EventLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!(
+            "EVENT_JSON:{}",
+            &near_sdk::serde_json::to_string(self).map_err(|_| fmt::Error)?
+        ))
+    }
+}
+
+/// An event log to capture tokens minting
+///
+/// Arguments
+/// * `owner_id`: "account.near"
+/// * `amount`: "100"
+/// * `memo`: optional message
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FtMintLog {
+    pub owner_id: String,
+    pub amount: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// An event log to capture tokens transfer
+///
+/// Arguments
+/// * `old_owner_id`: "owner.near"
+/// * `new_owner_id`: "receiver.near"
+/// * `amount`: "100"
+/// * `memo`: optional message
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FtTransferLog {
+    pub old_owner_id: String,
+    pub new_owner_id: String,
+    pub amount: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// An event log to capture tokens burning
+///
+/// Arguments
+/// * `owner_id`: "owner.near"
+/// * `amount`: "100"
+/// * `memo`: optional message
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FtBurnLog {
+    pub owner_id: String,
+    pub amount: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nep_format_mint() {
+        let expected = r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_mint","data":[{"owner_id":"foundation.near","amount":"500"}]}"#;
+        let log = EventLog {
+            standard: "nep141".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::FtMint(vec![FtMintLog {
+                owner_id: "foundation.near".to_owned(),
+                amount: "500".to_owned(),
+                memo: None,
+            }]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn nep_format_transfer() {
+        let expected = r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_transfer","data":[{"old_owner_id":"from.near","new_owner_id":"to.near","amount":"42","memo":"hi hello bonjour"}]}"#;
+        let log = EventLog {
+            standard: "nep141".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::FtTransfer(vec![FtTransferLog {
+                old_owner_id: "from.near".to_owned(),
+                new_owner_id: "to.near".to_owned(),
+                amount: "42".to_owned(),
+                memo: Some("hi hello bonjour".to_owned()),
+            }]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn nep_format_burn() {
+        let expected = r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_burn","data":[{"owner_id":"foundation.near","amount":"100"}]}"#;
+        let log = EventLog {
+            standard: "nep141".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::FtBurn(vec![FtBurnLog {
+                owner_id: "foundation.near".to_owned(),
+                amount: "100".to_owned(),
+                memo: None,
+            }]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+}
@@ -0,0 +1,157 @@
+use crate::*;
+use near_sdk::{ext_contract, Gas, PromiseResult};
+
+const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas(5_000_000_000_000);
+const GAS_FOR_FT_TRANSFER_CALL: Gas = Gas(30_000_000_000_000);
+
+pub trait FungibleTokenCore {
+    //transfers tokens to a receiver ID
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
+
+    //transfers tokens to a receiver and calls a function on the receiver ID's contract
+    /// Returns the amount of tokens that were used by the receiver.
+    fn ft_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<U128>;
+
+    //returns the total supply of the tokens
+    fn ft_total_supply(&self) -> U128;
+
+    //returns the balance of the account, 0 if it isn't registered
+    fn ft_balance_of(&self, account_id: AccountId) -> U128;
+}
+
+#[ext_contract(ext_fungible_token_receiver)]
+pub trait FungibleTokenReceiver {
+    //Method stored on the receiver contract that is called via cross contract call when ft_transfer_call is called
+    /// Returns the amount of unused tokens that should be returned to the sender.
+    fn ft_on_transfer(&mut self, sender_id: AccountId, amount: U128, msg: String) -> PromiseOrValue<U128>;
+}
+
+#[ext_contract(ext_self)]
+/*
+    resolves the promise of the cross contract call to the receiver contract
+    this is stored on THIS contract and is meant to analyze what happened in the cross contract call when ft_on_transfer was called
+    as part of the ft_transfer_call method
+*/
+pub trait FungibleTokenResolver {
+    fn ft_resolve_transfer(&mut self, sender_id: AccountId, receiver_id: AccountId, amount: U128) -> U128;
+}
+
+#[near_bindgen]
+impl FungibleTokenCore for Contract {
+    //implementation of the ft_transfer method. This transfers the tokens from the sender to the receiver.
+    #[payable]
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>) {
+        //assert that the user attached exactly 1 yoctoNEAR. This is for security and so that the user will be redirected to the NEAR wallet.
+        assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        self.internal_transfer(&sender_id, &receiver_id, amount.into(), memo);
+    }
+
+    //implementation of the transfer call method. This will transfer the tokens and call a method on the receiver_id contract
+    #[payable]
+    fn ft_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        //assert that the user attached exactly 1 yocto for security reasons.
+        assert_one_yocto();
+        assert!(env::prepaid_gas() > GAS_FOR_FT_TRANSFER_CALL, "More gas is required");
+        let sender_id = env::predecessor_account_id();
+        self.internal_transfer(&sender_id, &receiver_id, amount.into(), memo);
+
+        // Initiating receiver's call and the callback
+        ext_fungible_token_receiver::ext(receiver_id.clone())
+            .with_static_gas(env::prepaid_gas() - env::used_gas() - GAS_FOR_FT_TRANSFER_CALL)
+            .ft_on_transfer(sender_id.clone(), amount, msg)
+        // We then resolve the promise and call ft_resolve_transfer on our own contract
+        .then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(GAS_FOR_RESOLVE_TRANSFER)
+                .ft_resolve_transfer(sender_id, receiver_id, amount)
+        ).into()
+    }
+
+    fn ft_total_supply(&self) -> U128 {
+        U128(self.total_supply)
+    }
+
+    fn ft_balance_of(&self, account_id: AccountId) -> U128 {
+        U128(self.accounts.get(&account_id).unwrap_or(0))
+    }
+}
+
+#[near_bindgen]
+impl FungibleTokenResolver for Contract {
+    //resolves the cross contract call when calling ft_on_transfer in the ft_transfer_call method
+    //returns the amount of tokens that were used by the receiver
+    #[private]
+    fn ft_resolve_transfer(&mut self, sender_id: AccountId, receiver_id: AccountId, amount: U128) -> U128 {
+        let amount: Balance = amount.into();
+
+        //the receiver returns the amount it didn't use, if the call failed we refund everything
+        let unused_amount = match env::promise_result(0) {
+            PromiseResult::Successful(value) => {
+                if let Ok(unused_amount) = near_sdk::serde_json::from_slice::<U128>(&value) {
+                    std::cmp::min(amount, unused_amount.0)
+                } else {
+                    amount
+                }
+            }
+            _ => amount,
+        };
+
+        if unused_amount > 0 {
+            //the receiver may have spent the tokens in the meantime, so we only refund what's left of them
+            let receiver_balance = self.accounts.get(&receiver_id).unwrap_or(0);
+            if receiver_balance > 0 {
+                let refund_amount = std::cmp::min(receiver_balance, unused_amount);
+                self.accounts.insert(&receiver_id, &(receiver_balance - refund_amount));
+
+                if let Some(sender_balance) = self.accounts.get(&sender_id) {
+                    self.accounts.insert(&sender_id, &(sender_balance + refund_amount));
+
+                    let ft_transfer_log: EventLog = EventLog {
+                        // Standard name ("nep141").
+                        standard: FT_STANDARD_NAME.to_string(),
+                        // Version of the events standard ("1.0.0").
+                        version: FT_EVENTS_VERSION.to_string(),
+                        // The data related with the event stored in a vector.
+                        event: EventLogVariant::FtTransfer(vec![FtTransferLog {
+                            old_owner_id: receiver_id.to_string(),
+                            new_owner_id: sender_id.to_string(),
+                            amount: refund_amount.to_string(),
+                            memo: Some("refund".to_string()),
+                        }]),
+                    };
+                    env::log_str(&ft_transfer_log.to_string());
+
+                    return U128(amount - refund_amount);
+                } else {
+                    //the sender unregistered in the meantime, so the refund is burned
+                    self.total_supply -= refund_amount;
+
+                    let ft_burn_log: EventLog = EventLog {
+                        standard: FT_STANDARD_NAME.to_string(),
+                        version: FT_EVENTS_VERSION.to_string(),
+                        event: EventLogVariant::FtBurn(vec![FtBurnLog {
+                            owner_id: receiver_id.to_string(),
+                            amount: refund_amount.to_string(),
+                            memo: Some("refund".to_string()),
+                        }]),
+                    };
+                    env::log_str(&ft_burn_log.to_string());
+                }
+            }
+        }
+        U128(amount)
+    }
+}
@@ -0,0 +1,99 @@
+use crate::*;
+
+//used to make sure the user attached exactly 1 yoctoNEAR
+pub(crate) fn assert_one_yocto() {
+    assert_eq!(
+        env::attached_deposit(),
+        1,
+        "Requires attached deposit of exactly 1 yoctoNEAR",
+    )
+}
+
+impl Contract {
+    pub(crate) fn assert_called_by_owner(&self) {
+        //make sure the sender ID is the contract owner.
+        assert_eq!(
+            self.owner_id,
+            env::predecessor_account_id(),
+            "owner_id should be sender_id"
+        );
+    }
+
+    //measure the storage taken up by registering the longest possible account ID
+    pub(crate) fn measure_bytes_for_longest_account_id(&mut self) {
+        let initial_storage_usage = env::storage_usage();
+        let tmp_account_id = AccountId::new_unchecked("a".repeat(64));
+        self.accounts.insert(&tmp_account_id, &0u128);
+        self.bytes_for_longest_account_id = env::storage_usage() - initial_storage_usage;
+        self.accounts.remove(&tmp_account_id);
+    }
+
+    //get the balance of a registered account
+    pub(crate) fn internal_unwrap_balance_of(&self, account_id: &AccountId) -> Balance {
+        match self.accounts.get(account_id) {
+            Some(balance) => balance,
+            None => panic!("The account {} is not registered", account_id),
+        }
+    }
+
+    //register an account with an empty balance
+    pub(crate) fn internal_register_account(&mut self, account_id: &AccountId) {
+        if self.accounts.insert(account_id, &0).is_some() {
+            panic!("The account is already registered");
+        }
+    }
+
+    //add tokens to the balance of a registered account
+    pub(crate) fn internal_deposit(&mut self, account_id: &AccountId, amount: Balance) {
+        let balance = self.internal_unwrap_balance_of(account_id);
+        if let Some(new_balance) = balance.checked_add(amount) {
+            self.accounts.insert(account_id, &new_balance);
+        } else {
+            panic!("Balance overflow");
+        }
+    }
+
+    //take tokens out of the balance of a registered account
+    pub(crate) fn internal_withdraw(&mut self, account_id: &AccountId, amount: Balance) {
+        let balance = self.internal_unwrap_balance_of(account_id);
+        if let Some(new_balance) = balance.checked_sub(amount) {
+            self.accounts.insert(account_id, &new_balance);
+        } else {
+            panic!("The account doesn't have enough balance");
+        }
+    }
+
+    //move tokens between registered accounts and log the transfer
+    pub(crate) fn internal_transfer(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+        memo: Option<String>,
+    ) {
+        //we make sure that the sender isn't sending the tokens to themselves
+        assert_ne!(sender_id, receiver_id, "Sender and receiver should be different");
+        assert!(amount > 0, "The amount should be a positive number");
+
+        self.internal_withdraw(sender_id, amount);
+        self.internal_deposit(receiver_id, amount);
+
+        // Construct the transfer log as per the events standard.
+        let ft_transfer_log: EventLog = EventLog {
+            // Standard name ("nep141").
+            standard: FT_STANDARD_NAME.to_string(),
+            // Version of the events standard ("1.0.0").
+            version: FT_EVENTS_VERSION.to_string(),
+            // The data related with the event stored in a vector.
+            event: EventLogVariant::FtTransfer(vec![FtTransferLog {
+                old_owner_id: sender_id.to_string(),
+                new_owner_id: receiver_id.to_string(),
+                amount: amount.to_string(),
+                memo,
+            }]),
+        };
+
+        // Log the serialized json.
+        env::log_str(&ft_transfer_log.to_string());
+    }
+}
@@ -0,0 +1,144 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LazyOption, LookupMap, UnorderedSet};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{
+    env, near_bindgen, AccountId, Balance, PanicOnDefault, Promise, PromiseOrValue, StorageUsage,
+};
+
+use crate::internal::*;
+pub use crate::metadata::*;
+pub use crate::ft_core::*;
+pub use crate::storage::*;
+pub use crate::events::*;
+
+mod internal;
+mod metadata;
+mod ft_core;
+mod storage;
+mod events;
+mod mint;
+
+/// This spec can be treated like a version of the standard.
+pub const FT_METADATA_SPEC: &str = "ft-1.0.0";
+/// This is the name of the FT standard we're using
+pub const FT_STANDARD_NAME: &str = "nep141";
+/// This is the version of the events of the FT standard
+pub const FT_EVENTS_VERSION: &str = "1.0.0";
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    //contract owner
+    pub owner_id: AccountId,
+
+    //keeps track of the balance of every registered account
+    pub accounts: LookupMap<AccountId, Balance>,
+
+    //total amount of resources in circulation
+    pub total_supply: Balance,
+
+    //storage taken up by registering the longest possible account ID
+    pub bytes_for_longest_account_id: StorageUsage,
+
+    //keeps track of the metadata for the contract
+    pub metadata: LazyOption<FungibleTokenMetadata>,
+
+    //location contracts allowed to mint resources
+    pub locations: UnorderedSet<AccountId>,
+}
+
+/// Helper structure for keys of the persistent collections.
+#[derive(BorshSerialize)]
+pub enum StorageKey {
+    Accounts,
+    Metadata,
+    Locations,
+}
+
+#[near_bindgen]
+impl Contract {
+    /*
+        initialization function (can only be called once).
+        this initializes the contract with default metadata so the
+        user doesn't have to manually type metadata.
+    */
+    #[init]
+    pub fn new_default_meta(owner_id: AccountId, locations: Option<Vec<AccountId>>) -> Self {
+        //calls the other function "new: with some default metadata and the owner_id & locations passed in
+        Self::new(
+            owner_id,
+            FungibleTokenMetadata {
+                spec: FT_METADATA_SPEC.to_string(),
+                name: "Pray Resource".to_string(),
+                symbol: "PRAY".to_string(),
+                icon: None,
+                reference: None,
+                reference_hash: None,
+                decimals: 0,
+            },
+            locations,
+        )
+    }
+
+    /*
+        initialization function (can only be called once).
+        this initializes the contract with metadata that was passed in and
+        the owner_id and the location contracts allowed to mint.
+    */
+    #[init]
+    pub fn new(
+        owner_id: AccountId,
+        metadata: FungibleTokenMetadata,
+        locations: Option<Vec<AccountId>>,
+    ) -> Self {
+        metadata.assert_valid();
+
+        //create a variable of type Self with all the fields initialized.
+        let mut this = Self {
+            owner_id,
+            accounts: LookupMap::new(StorageKey::Accounts.try_to_vec().unwrap()),
+            total_supply: 0,
+            bytes_for_longest_account_id: 0,
+            metadata: LazyOption::new(
+                StorageKey::Metadata.try_to_vec().unwrap(),
+                Some(&metadata),
+            ),
+            locations: UnorderedSet::new(StorageKey::Locations.try_to_vec().unwrap()),
+        };
+        for location_id in locations.unwrap_or_default() {
+            this.locations.insert(&location_id);
+        }
+        this.measure_bytes_for_longest_account_id();
+
+        //return the Contract object
+        this
+    }
+
+    //allow a location contract to mint resources
+    pub fn register_location(&mut self, location_id: AccountId) {
+        self.assert_called_by_owner();
+
+        self.locations.insert(&location_id);
+    }
+
+    pub fn unregister_location(&mut self, location_id: AccountId) {
+        self.assert_called_by_owner();
+
+        assert!(self.locations.remove(&location_id), "Location is not registered");
+    }
+
+    //paginate through the location contracts allowed to mint resources
+    pub fn locations(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<AccountId> {
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+
+        self.locations
+            .iter()
+            .skip(start as usize)
+            .take(limit.unwrap_or(50) as usize)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests;
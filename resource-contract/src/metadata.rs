@@ -0,0 +1,36 @@
+use crate::*;
+use near_sdk::json_types::Base64VecU8;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FungibleTokenMetadata {
+    pub spec: String,              // required, essentially a version like "ft-1.0.0"
+    pub name: String,              // required, ex. "Wrapped Ether"
+    pub symbol: String,            // required, ex. "WETH"
+    pub icon: Option<String>,      // Data URL
+    pub reference: Option<String>, // URL to a JSON file with more info
+    pub reference_hash: Option<Base64VecU8>, // Base64-encoded sha256 hash of JSON from reference field. Required if `reference` is included.
+    pub decimals: u8,              // used in frontends to show the proper significant digits of a token
+}
+
+impl FungibleTokenMetadata {
+    pub fn assert_valid(&self) {
+        assert_eq!(&self.spec, FT_METADATA_SPEC, "Invalid metadata spec");
+        assert_eq!(self.reference.is_some(), self.reference_hash.is_some(), "Reference hash is required with the reference");
+        if let Some(reference_hash) = &self.reference_hash {
+            assert_eq!(reference_hash.0.len(), 32, "Hash has to be 32 bytes");
+        }
+    }
+}
+
+pub trait FungibleTokenMetadataProvider {
+    //view call for returning the contract metadata
+    fn ft_metadata(&self) -> FungibleTokenMetadata;
+}
+
+#[near_bindgen]
+impl FungibleTokenMetadataProvider for Contract {
+    fn ft_metadata(&self) -> FungibleTokenMetadata {
+        self.metadata.get().unwrap()
+    }
+}
@@ -0,0 +1,33 @@
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    //mint resources into a registered account, can only be called by a registered location contract
+    pub fn ft_mint(&mut self, account_id: AccountId, amount: U128, memo: Option<String>) {
+        assert!(
+            self.locations.contains(&env::predecessor_account_id()),
+            "Can only be called by a registered location contract"
+        );
+        assert!(amount.0 > 0, "The amount should be a positive number");
+
+        self.internal_deposit(&account_id, amount.0);
+        self.total_supply = self.total_supply.checked_add(amount.0).expect("Total supply overflow");
+
+        // Construct the mint log as per the events standard.
+        let ft_mint_log: EventLog = EventLog {
+            // Standard name ("nep141").
+            standard: FT_STANDARD_NAME.to_string(),
+            // Version of the events standard ("1.0.0").
+            version: FT_EVENTS_VERSION.to_string(),
+            // The data related with the event stored in a vector.
+            event: EventLogVariant::FtMint(vec![FtMintLog {
+                owner_id: account_id.to_string(),
+                amount: amount.0.to_string(),
+                memo,
+            }]),
+        };
+
+        // Log the serialized json.
+        env::log_str(&ft_mint_log.to_string());
+    }
+}
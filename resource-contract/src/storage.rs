@@ -0,0 +1,124 @@
+use crate::*;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageBalance {
+    pub total: U128,
+    pub available: U128,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageBalanceBounds {
+    pub min: U128,
+    pub max: Option<U128>,
+}
+
+pub trait StorageManagement {
+    //registers an account, paying for its storage with the attached deposit
+    fn storage_deposit(&mut self, account_id: Option<AccountId>, registration_only: Option<bool>) -> StorageBalance;
+
+    //withdraws the storage deposit that isn't used, which is always 0 since the storage cost is fixed
+    fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance;
+
+    //unregisters the account and refunds its storage deposit, burning its balance if forced
+    fn storage_unregister(&mut self, force: Option<bool>) -> bool;
+
+    fn storage_balance_bounds(&self) -> StorageBalanceBounds;
+
+    fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance>;
+}
+
+#[near_bindgen]
+impl StorageManagement for Contract {
+    #[payable]
+    #[allow(unused_variables)]
+    fn storage_deposit(&mut self, account_id: Option<AccountId>, registration_only: Option<bool>) -> StorageBalance {
+        let amount = env::attached_deposit();
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        if self.accounts.contains_key(&account_id) {
+            env::log_str("The account is already registered, refunding the deposit");
+            if amount > 0 {
+                Promise::new(env::predecessor_account_id()).transfer(amount);
+            }
+        } else {
+            let min_balance = self.storage_balance_bounds().min.0;
+            assert!(amount >= min_balance, "The attached deposit is less than the minimum storage balance");
+
+            self.internal_register_account(&account_id);
+            //the storage cost is fixed, so anything above it is refunded
+            let refund = amount - min_balance;
+            if refund > 0 {
+                Promise::new(env::predecessor_account_id()).transfer(refund);
+            }
+        }
+        self.storage_balance_of(account_id).unwrap()
+    }
+
+    #[payable]
+    fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
+        assert_one_yocto();
+        let predecessor_account_id = env::predecessor_account_id();
+        if let Some(storage_balance) = self.storage_balance_of(predecessor_account_id.clone()) {
+            if let Some(amount) = amount {
+                assert_eq!(amount.0, 0, "The amount is greater than the available storage balance");
+            }
+            storage_balance
+        } else {
+            panic!("The account {} is not registered", predecessor_account_id);
+        }
+    }
+
+    #[payable]
+    fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let force = force.unwrap_or(false);
+        if let Some(balance) = self.accounts.get(&account_id) {
+            assert!(balance == 0 || force, "Can't unregister the account with a positive balance without force");
+
+            self.accounts.remove(&account_id);
+            self.total_supply -= balance;
+            Promise::new(account_id.clone()).transfer(self.storage_balance_bounds().min.0 + 1);
+
+            if balance > 0 {
+                let ft_burn_log: EventLog = EventLog {
+                    // Standard name ("nep141").
+                    standard: FT_STANDARD_NAME.to_string(),
+                    // Version of the events standard ("1.0.0").
+                    version: FT_EVENTS_VERSION.to_string(),
+                    // The data related with the event stored in a vector.
+                    event: EventLogVariant::FtBurn(vec![FtBurnLog {
+                        owner_id: account_id.to_string(),
+                        amount: balance.to_string(),
+                        memo: None,
+                    }]),
+                };
+                env::log_str(&ft_burn_log.to_string());
+            }
+            true
+        } else {
+            env::log_str(&format!("The account {} is not registered", account_id));
+            false
+        }
+    }
+
+    fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        let required_storage_balance = Balance::from(self.bytes_for_longest_account_id) * env::storage_byte_cost();
+        StorageBalanceBounds {
+            min: required_storage_balance.into(),
+            max: Some(required_storage_balance.into()),
+        }
+    }
+
+    fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+        if self.accounts.contains_key(&account_id) {
+            Some(StorageBalance {
+                total: self.storage_balance_bounds().min,
+                available: 0.into(),
+            })
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,185 @@
+/* unit tests */
+use crate::Contract;
+use crate::{FungibleTokenCore, FungibleTokenMetadataProvider, FungibleTokenResolver, StorageManagement};
+use near_sdk::json_types::U128;
+use near_sdk::test_utils::{accounts, VMContextBuilder};
+use near_sdk::{env, testing_env, AccountId, PromiseResult, RuntimeFeesConfig, VMConfig};
+
+fn get_context(predecessor: AccountId) -> VMContextBuilder {
+    let mut builder = VMContextBuilder::new();
+    builder.current_account_id(accounts(0)).predecessor_account_id(predecessor);
+    builder
+}
+
+//contract owned by alice, where danny is a registered location and bob and charlie are registered accounts
+fn get_contract(context: &mut VMContextBuilder) -> Contract {
+    testing_env!(context.predecessor_account_id(accounts(0)).build());
+    let mut contract = Contract::new_default_meta(accounts(0), Some(vec![accounts(3)]));
+
+    let storage_cost = contract.storage_balance_bounds().min.0;
+    for account_id in [accounts(1), accounts(2)] {
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(storage_cost)
+            .predecessor_account_id(account_id)
+            .build());
+        contract.storage_deposit(None, None);
+    }
+    contract
+}
+
+#[test]
+fn test_new() {
+    let context = get_context(accounts(0));
+    testing_env!(context.build());
+    let contract = Contract::new_default_meta(accounts(0), Some(vec![accounts(3)]));
+    assert_eq!(contract.ft_metadata().symbol, "PRAY");
+    assert_eq!(contract.ft_total_supply(), U128(0));
+    assert_eq!(contract.locations(None, None), vec![accounts(3)]);
+    assert!(contract.storage_balance_bounds().min.0 > 0);
+}
+
+#[test]
+fn test_storage_deposit() {
+    let mut context = get_context(accounts(0));
+    let contract = get_contract(&mut context);
+    let storage_balance = contract.storage_balance_of(accounts(1)).unwrap();
+    assert_eq!(storage_balance.total, contract.storage_balance_bounds().min);
+    assert_eq!(storage_balance.available, U128(0));
+    assert_eq!(contract.storage_balance_of(accounts(4)), None);
+}
+
+#[test]
+#[should_panic(expected = "The attached deposit is less than the minimum storage balance")]
+fn test_storage_deposit_not_enough() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_contract(&mut context);
+
+    testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(4)).build());
+    contract.storage_deposit(None, None);
+}
+
+#[test]
+fn test_ft_mint() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_contract(&mut context);
+
+    testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(3)).build());
+    contract.ft_mint(accounts(1), U128(100), None);
+    assert_eq!(contract.ft_balance_of(accounts(1)), U128(100));
+    assert_eq!(contract.ft_total_supply(), U128(100));
+    assert_eq!(
+        near_sdk::test_utils::get_logs(),
+        vec![r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_mint","data":[{"owner_id":"bob","amount":"100"}]}"#]
+    );
+}
+
+#[test]
+#[should_panic(expected = "Can only be called by a registered location contract")]
+fn test_ft_mint_not_location() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_contract(&mut context);
+
+    testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(1)).build());
+    contract.ft_mint(accounts(1), U128(100), None);
+}
+
+#[test]
+#[should_panic(expected = "The account eugene is not registered")]
+fn test_ft_mint_not_registered() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_contract(&mut context);
+
+    testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(3)).build());
+    contract.ft_mint(accounts(4), U128(100), None);
+}
+
+#[test]
+#[should_panic(expected = "Can only be called by a registered location contract")]
+fn test_unregister_location() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_contract(&mut context);
+
+    testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(0)).build());
+    contract.unregister_location(accounts(3));
+    assert!(contract.locations(None, None).is_empty());
+
+    testing_env!(context.predecessor_account_id(accounts(3)).build());
+    contract.ft_mint(accounts(1), U128(100), None);
+}
+
+#[test]
+fn test_ft_transfer() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_contract(&mut context);
+
+    testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(3)).build());
+    contract.ft_mint(accounts(1), U128(100), None);
+
+    testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(1)).build());
+    contract.ft_transfer(accounts(2), U128(30), None);
+    assert_eq!(contract.ft_balance_of(accounts(1)), U128(70));
+    assert_eq!(contract.ft_balance_of(accounts(2)), U128(30));
+    assert_eq!(contract.ft_total_supply(), U128(100));
+}
+
+#[test]
+#[should_panic(expected = "The account doesn't have enough balance")]
+fn test_ft_transfer_not_enough_balance() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_contract(&mut context);
+
+    testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(1)).build());
+    contract.ft_transfer(accounts(2), U128(30), None);
+}
+
+#[test]
+fn test_ft_resolve_transfer_refund() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_contract(&mut context);
+
+    testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(3)).build());
+    contract.ft_mint(accounts(1), U128(100), None);
+
+    testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(1)).build());
+    contract.ft_transfer(accounts(2), U128(30), None);
+
+    //the receiver only used 10 of the 30 tokens
+    testing_env!(
+        context.attached_deposit(0).predecessor_account_id(accounts(0)).build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        Default::default(),
+        vec![PromiseResult::Successful(b"\"20\"".to_vec())]
+    );
+    assert_eq!(contract.ft_resolve_transfer(accounts(1), accounts(2), U128(30)), U128(10));
+    assert_eq!(contract.ft_balance_of(accounts(1)), U128(90));
+    assert_eq!(contract.ft_balance_of(accounts(2)), U128(10));
+}
+
+#[test]
+#[should_panic(expected = "Can't unregister the account with a positive balance without force")]
+fn test_storage_unregister_positive_balance() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_contract(&mut context);
+
+    testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(3)).build());
+    contract.ft_mint(accounts(1), U128(100), None);
+
+    testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(1)).build());
+    contract.storage_unregister(None);
+}
+
+#[test]
+fn test_storage_unregister_force() {
+    let mut context = get_context(accounts(0));
+    let mut contract = get_contract(&mut context);
+
+    testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(3)).build());
+    contract.ft_mint(accounts(1), U128(100), None);
+
+    testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(1)).build());
+    assert!(contract.storage_unregister(Some(true)));
+    assert_eq!(contract.storage_balance_of(accounts(1)), None);
+    assert_eq!(contract.ft_total_supply(), U128(0));
+}